@@ -0,0 +1,27 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::time::Instant;
+use dingtalk::*;
+use common::*;
+
+const ROUNDS: u32 = 200;
+
+fn main() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let json_message = r#"{"msgtype":"text","text":{"content":"bench"}}"#;
+    tokio_test::block_on(async {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            dt.send(json_message).await.unwrap();
+        }
+        println!("send:      {:?}/iter", start.elapsed() / ROUNDS);
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            dt.send_fast(json_message).await.unwrap();
+        }
+        println!("send_fast: {:?}/iter", start.elapsed() / ROUNDS);
+    });
+}
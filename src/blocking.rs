@@ -0,0 +1,139 @@
+use std::{ sync::OnceLock, thread };
+use crate::{ DingTalk, DingTalkError, DingTalkMessage, SendOptions, SendOutcome, XResult };
+use crate::transport::{ build_headers, check_status };
+
+/// Blocking `DingTalk`, created by `DingTalk::blocking`, requires feature `blocking`
+/// 
+/// Shares message JSON building, URL signing, rate limit and retry policy with `DingTalk`,
+/// waits with `std::thread::sleep`, MUST NOT be used inside an async runtime.
+/// 
+/// Sends with `DingTalk::blocking_client`, created by `DingTalkBuilder` with the same proxy as the async client,
+/// a custom async `client` can not be used, blocking sends fail unless `blocking_client` is set too.
+/// 
+/// ```ignore
+/// let dt = DingTalk::new("<token>", "");
+/// dt.blocking().send_text("Hello world!")?;
+/// ```
+pub struct BlockingDingTalk<'a> {
+    dingtalk: &'a DingTalk,
+}
+
+impl DingTalk {
+
+    /// Blocking `DingTalk`, for non-async code, e.g. CLI tools
+    pub fn blocking(&self) -> BlockingDingTalk<'_> {
+        BlockingDingTalk { dingtalk: self }
+    }
+}
+
+impl<'a> BlockingDingTalk<'a> {
+
+    /// Send DingTalk message, see `DingTalk::send_message`
+    pub fn send_message(&self, dingtalk_message: DingTalkMessage) -> XResult<()> {
+        let message_type = dingtalk_message.kind();
+        let result = self.dingtalk.check_keywords(&dingtalk_message)
+            .and_then(|_| self.dingtalk.build_message_json(&dingtalk_message))
+            .and_then(|message_json| self.send(&message_json));
+        if let Err(e) = &result {
+            trace_event!(warn, message_type = ?message_type, error = %e, "send DingTalk message failed");
+            if let Some(dead_letter) = &self.dingtalk.dead_letter {
+                if let Some(diagnostic_json) = self.dingtalk.dead_letter_json(message_type, e) {
+                    let _ = dead_letter.blocking().send(&diagnostic_json);
+                }
+            }
+        }
+        result
+    }
+
+    /// Send text message
+    pub fn send_text(&self, text_message: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_text(text_message))
+    }
+
+    /// Send text message and @ all
+    pub fn send_text_at_all(&self, text_message: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_text(text_message).at_all())
+    }
+
+    /// Send markdown message
+    pub fn send_markdown(&self, title: &str, text: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_markdown(title, text))
+    }
+
+    /// Send markdown message and @ all
+    pub fn send_markdown_at_all(&self, title: &str, text: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_markdown(title, text).at_all())
+    }
+
+    /// Direct send JSON message
+    pub fn send(&self, json_message: &str) -> XResult<()> {
+        self.send_with_outcome(json_message)?;
+        Ok(())
+    }
+
+    /// Direct send JSON message, returns `SendOutcome`
+    pub fn send_with_outcome(&self, json_message: &str) -> XResult<SendOutcome> {
+        if self.dingtalk.is_duplicate(&self.dingtalk.access_token, json_message) {
+            return Ok(SendOutcome { deduplicated: true, ..self.dingtalk.send_outcome() });
+        }
+        #[cfg(feature = "tracing")]
+        let _span = self.dingtalk.send_span().entered();
+        let mut retries = 0;
+        loop {
+            while let Some(wait) = self.dingtalk.try_acquire_rate_limit()? {
+                thread::sleep(wait);
+            }
+            let err = match self.send_once(json_message) {
+                Ok(o) => {
+                    self.dingtalk.record_sent(&self.dingtalk.access_token, json_message);
+                    return Ok(o);
+                },
+                Err(e) => e,
+            };
+            match self.dingtalk.retry_delay(retries, &err) {
+                Some(delay) => {
+                    trace_event!(info, retry = retries + 1, delay_ms = delay.as_millis() as u64, error = %err, "retry DingTalk send");
+                    thread::sleep(delay)
+                },
+                None => return Err(err),
+            }
+            retries += 1;
+        }
+    }
+
+    fn send_once(&self, json_message: &str) -> XResult<SendOutcome> {
+        let signed_url = self.dingtalk.generate_signed_url()?;
+        if self.dingtalk.skip_send(json_message) {
+            return Ok(self.dingtalk.send_outcome());
+        }
+        if let Some(transport) = &self.dingtalk.transport {
+            let headers = build_headers(&self.dingtalk.headers, &SendOptions::default())?;
+            let body = futures::executor::block_on(transport.post(&signed_url, &headers, json_message))?;
+            self.dingtalk.check_response(&body)?;
+            return Ok(self.dingtalk.send_outcome());
+        }
+        let mut request = self.client()?.post(&signed_url)
+              .headers(build_headers(&self.dingtalk.headers, &SendOptions::default())?)
+              .body(json_message.as_bytes().to_vec());
+        if let Some(timeout) = self.dingtalk.timeout {
+            request = request.timeout(timeout);
+        }
+        trace_event!(debug, body_length = json_message.len(), "POST DingTalk message");
+        let response = request.send()?;
+        trace_event!(debug, status = response.status().as_u16(), "DingTalk response");
+        check_status(response.status().as_u16(), response.headers())?;
+        self.dingtalk.check_response(&response.text()?)?;
+        Ok(self.dingtalk.send_outcome())
+    }
+
+    fn client(&self) -> XResult<&reqwest::blocking::Client> {
+        static SHARED_CLIENT: OnceLock<reqwest::blocking::Client> = OnceLock::new();
+        match (&self.dingtalk.blocking_client, &self.dingtalk.client) {
+            (Some(blocking_client), _) => Ok(blocking_client),
+            // proxy or TLS config of custom client would be silently ignored
+            (None, Some(_)) => Err(DingTalkError::InvalidConfig(
+                "blocking send does not use custom client, set DingTalkBuilder::blocking_client".into())),
+            (None, None) => Ok(SHARED_CLIENT.get_or_init(reqwest::blocking::Client::new)),
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use std::{ sync::Arc, time::Duration };
+use reqwest::header::{ HeaderName, HeaderValue, CONTENT_TYPE };
+use crate::{ Clock, Deduplicator, DingTalk, DingTalkError, DingTalkType, DingTalkWebhookParams, RateLimiter, RetryPolicy, Sleep, Transport, XResult };
+use crate::config::default_webhook_url;
+
+/// `DingTalk` builder, one place to set every option
+/// 
+/// ```ignore
+/// let dt = DingTalk::builder()
+///     .access_token("<token>")
+///     .sec_token("<sec token>")
+///     .timeout(Duration::from_secs(10))
+///     .rate_limit(20)
+///     .retries(3, Duration::from_millis(500))
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct DingTalkBuilder {
+    dingtalk: DingTalk,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<XResult<reqwest::Proxy>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    use_system_proxy: Option<bool>,
+    base_host: Option<String>,
+    rate_limit_error: Option<DingTalkError>,
+    headers: Vec<(String, String)>,
+    allow_content_type_override: bool,
+    content_type: Option<String>,
+}
+
+impl DingTalk {
+
+    /// Create `DingTalkBuilder`
+    pub fn builder() -> DingTalkBuilder {
+        DingTalkBuilder::default()
+    }
+}
+
+impl DingTalkBuilder {
+
+    /// Set type, DingTalk or WeChat Work, default is DingTalk
+    pub fn dingtalk_type(mut self, dingtalk_type: DingTalkType) -> Self {
+        self.dingtalk.dingtalk_type = dingtalk_type;
+        self
+    }
+
+    /// Set access token(key of WeChat Work)
+    pub fn access_token(mut self, access_token: &str) -> Self {
+        self.dingtalk.access_token = access_token.into();
+        self
+    }
+
+    /// Set sec token, request is signed when sec token is not empty
+    pub fn sec_token(mut self, sec_token: &str) -> Self {
+        self.dingtalk.sec_token = sec_token.into();
+        self
+    }
+
+    /// Set access token is already URL encoded, e.g. copied from webhook URL, it is not encoded again, default `false`
+    pub fn token_pre_encoded(mut self, token_pre_encoded: bool) -> Self {
+        self.dingtalk.token_pre_encoded = token_pre_encoded;
+        self
+    }
+
+    /// Set webhook url, default is the official robot url of type
+    pub fn webhook_url(mut self, webhook_url: &str) -> Self {
+        self.dingtalk.default_webhook_url = webhook_url.into();
+        self
+    }
+
+    /// Set webhook url to the robot path of type on `base_host`, validated in `build`, see `DingTalk::set_base_host`
+    pub fn base_host(mut self, base_host: &str) -> Self {
+        self.base_host = Some(base_host.into());
+        self
+    }
+
+    /// Set direct url, for outgoing robot, sent as is without token and sign
+    pub fn direct_url(mut self, direct_url: &str) -> Self {
+        self.dingtalk.direct_url = direct_url.into();
+        self
+    }
+
+    /// Allow http webhook URL, default only https is allowed, `build` and send return `DingTalkError::InvalidConfig`
+    /// 
+    /// Token and sign are sent in cleartext over http, ONLY for local mock testing
+    pub fn allow_insecure(mut self) -> Self {
+        self.dingtalk.allow_insecure = true;
+        self
+    }
+
+    /// Set webhook URL query param names
+    pub fn webhook_params(mut self, webhook_params: DingTalkWebhookParams) -> Self {
+        self.dingtalk.webhook_params = webhook_params;
+        self
+    }
+
+    /// Set custom keywords, see `DingTalk::with_keywords`
+    pub fn keywords(mut self, keywords: Vec<String>) -> Self {
+        self.dingtalk.keywords = keywords;
+        self
+    }
+
+    /// Message without keyword is `DingTalkError::InvalidMessage`, see `DingTalk::with_required_keywords`
+    pub fn keywords_required(mut self, keywords_required: bool) -> Self {
+        self.dingtalk.keywords_required = keywords_required;
+        self
+    }
+
+    /// Set request timeout, timeout is `DingTalkError::Timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.dingtalk.timeout = Some(timeout);
+        self
+    }
+
+    /// Set client side rate limit, messages per minute, 0 is `DingTalkError::InvalidConfig` when `build`
+    pub fn rate_limit(mut self, per_minute: u32) -> Self {
+        match RateLimiter::per_minute(per_minute) {
+            Ok(rate_limiter) => self.rate_limiter(Arc::new(rate_limiter)),
+            Err(e) => {
+                self.rate_limit_error = Some(e);
+                self
+            },
+        }
+    }
+
+    /// Set client side rate limiter
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.dingtalk.rate_limiter = Some(rate_limiter);
+        self.rate_limit_error = None;
+        self
+    }
+
+    /// Set dedup, a message identical to one sent within `window` is not sent, not available in wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn dedup(self, window: Duration) -> Self {
+        self.deduplicator(Arc::new(Deduplicator::new(window)))
+    }
+
+    /// Set deduplicator
+    pub fn deduplicator(mut self, deduplicator: Arc<Deduplicator>) -> Self {
+        self.dingtalk.deduplicator = Some(deduplicator);
+        self
+    }
+
+    /// Set retries with exponential backoff starting from `base_delay`
+    pub fn retries(self, max_retries: u32, base_delay: Duration) -> Self {
+        self.retry_policy(RetryPolicy::new(max_retries, base_delay))
+    }
+
+    /// Set retry policy
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.dingtalk.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Set sleep, used when waiting for rate limiter and between retries, required by retries in wasm
+    pub fn sleep(mut self, sleep: Arc<dyn Sleep>) -> Self {
+        self.dingtalk.sleep = Some(sleep);
+        self
+    }
+
+    /// Set clock, used when signing
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.dingtalk.clock = Some(clock);
+        self
+    }
+
+    /// Set transport, e.g. `MockTransport` in unit tests, HTTP client options(proxy, client) are not used by it
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.dingtalk.transport = Some(transport);
+        self
+    }
+
+    /// Set dry run, `send` generates signed URL but does not POST, returns `Ok`,
+    /// JSON message is logged with feature `tracing`
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dingtalk.dry_run = dry_run;
+        self
+    }
+
+    /// Set auto append at mentions, `send_message` appends `@<mobile>` and `@<user id>` to text and markdown content
+    pub fn auto_append_at(mut self, auto_append_at: bool) -> Self {
+        self.dingtalk.auto_append_at = auto_append_at;
+        self
+    }
+
+    /// Set cancellation token, when it is cancelled in-flight and later sends(including waiting for retries and rate limiter)
+    /// are aborted with `DingTalkError::Cancelled`, whichever of cancellation and `timeout` fires first wins
+    /// 
+    /// `BlockingDingTalk` is not cancelled
+    #[cfg(feature = "cancellation")]
+    pub fn cancellation_token(mut self, cancellation_token: crate::CancellationToken) -> Self {
+        self.dingtalk.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Add header to every request, e.g. API key of gateway, validated in `build`
+    /// 
+    /// `Content-Type` is rejected unless `allow_content_type_override(true)`, per send `SendOptions::header` takes precedence
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Allow `with_header` overriding `Content-Type`, default `false`
+    pub fn allow_content_type_override(mut self, allow_content_type_override: bool) -> Self {
+        self.allow_content_type_override = allow_content_type_override;
+        self
+    }
+
+    /// Set `Content-Type` of every request, e.g. `application/json` for strict gateways,
+    /// default is `application/json; charset=utf-8`, must be a JSON media type, validated in `build`
+    pub fn with_content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Set dead letter robot
+    pub fn dead_letter(mut self, dead_letter: DingTalk) -> Self {
+        self.dingtalk.dead_letter = Some(Box::new(dead_letter));
+        self
+    }
+
+    /// Set HTTP proxy, ignored when `client` is set, not available in wasm
+    /// 
+    /// Without proxy, `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables are used
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(Ok(proxy));
+        self.dingtalk.proxy_url = None;
+        self
+    }
+
+    /// Set HTTP proxy for all requests, e.g. `http://proxy.example.com:8080`,
+    /// invalid proxy URL is `DingTalkError::InvalidConfig` when `build`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_proxy(mut self, proxy_url: &str) -> Self {
+        self.proxy = Some(reqwest::Proxy::all(proxy_url)
+            .map_err(|e| DingTalkError::InvalidConfig(format!("invalid proxy URL: {}", e))));
+        self.dingtalk.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    /// Use system proxy(`HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`) or not, default is reqwest default, which uses it
+    /// 
+    /// `false` guarantees direct connections, e.g. in a service mesh, proxy set by `proxy` is still used
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn use_system_proxy(mut self, use_system_proxy: bool) -> Self {
+        self.use_system_proxy = Some(use_system_proxy);
+        self
+    }
+
+    /// Set HTTP client
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.dingtalk.client = Some(client);
+        self
+    }
+
+    /// Set HTTP client of `BlockingDingTalk`, required by blocking sends when `client` is set
+    #[cfg(feature = "blocking")]
+    pub fn blocking_client(mut self, blocking_client: reqwest::blocking::Client) -> Self {
+        self.dingtalk.blocking_client = Some(blocking_client);
+        self
+    }
+
+    /// Build `DingTalk`, fails when HTTP client with proxy cannot be created
+    /// 
+    /// Empty `access_token` without `direct_url` is `DingTalkError::InvalidConfig("missing access_token")`,
+    /// `proxy` and `use_system_proxy` are ignored when `client` is set
+    pub fn build(mut self) -> XResult<DingTalk> {
+        if let Some(e) = self.rate_limit_error.take() {
+            return Err(e);
+        }
+        if let Some(base_host) = &self.base_host {
+            self.dingtalk.set_base_host(base_host)?;
+        }
+        self.dingtalk.check_access_token()?;
+        self.dingtalk.check_webhook_url_scheme()?;
+        self.check_sleep()?;
+        self.build_headers()?;
+        self.build_proxy_client()?;
+        Ok(self.build_without_client())
+    }
+
+    fn build_headers(&mut self) -> XResult<()> {
+        if let Some(content_type) = &self.content_type {
+            self.dingtalk.headers.insert(CONTENT_TYPE, json_content_type(content_type)?);
+        }
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| DingTalkError::InvalidConfig(format!("invalid header name: {}", name)))?;
+            // value may be a secret(e.g. API key) and is not in error
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| DingTalkError::InvalidConfig(format!("invalid header value of: {}", name)))?;
+            if header_name == CONTENT_TYPE && !self.allow_content_type_override {
+                return Err(DingTalkError::InvalidConfig(
+                    "overriding header Content-Type requires allow_content_type_override(true)".into()));
+            }
+            self.dingtalk.headers.insert(header_name, header_value);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_sleep(&self) -> XResult<()> {
+        Ok(())
+    }
+
+    /// `DefaultSleep` is not available in browser, retries wait with `sleep` only
+    #[cfg(target_arch = "wasm32")]
+    fn check_sleep(&self) -> XResult<()> {
+        if self.dingtalk.retry_policy.is_some() && self.dingtalk.sleep.is_none() {
+            return Err(DingTalkError::InvalidConfig("retries in wasm require DingTalkBuilder::sleep".into()));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn build_proxy_client(&mut self) -> XResult<()> {
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build_proxy_client(&mut self) -> XResult<()> {
+        let no_system_proxy = self.use_system_proxy == Some(false);
+        if (self.proxy.is_some() || no_system_proxy) && self.dingtalk.client.is_none() {
+            let mut client_builder = reqwest::Client::builder();
+            if no_system_proxy {
+                client_builder = client_builder.no_proxy();
+            }
+            let proxy = self.proxy.take().transpose()?;
+            if let Some(proxy) = &proxy {
+                client_builder = client_builder.proxy(proxy.clone());
+            }
+            let client = client_builder.build()
+                .map_err(|e| DingTalkError::InvalidConfig(format!("create HTTP client failed: {}", e)))?;
+            self.dingtalk.client = Some(client);
+            #[cfg(feature = "blocking")]
+            self.build_blocking_client(proxy, no_system_proxy)?;
+        }
+        Ok(())
+    }
+
+    /// Blocking client with the same proxy as async client, unless set by `blocking_client`
+    #[cfg(feature = "blocking")]
+    fn build_blocking_client(&mut self, proxy: Option<reqwest::Proxy>, no_system_proxy: bool) -> XResult<()> {
+        if self.dingtalk.blocking_client.is_some() {
+            return Ok(());
+        }
+        let mut client_builder = reqwest::blocking::Client::builder();
+        if no_system_proxy {
+            client_builder = client_builder.no_proxy();
+        }
+        if let Some(proxy) = proxy {
+            client_builder = client_builder.proxy(proxy);
+        }
+        let client = client_builder.build()
+            .map_err(|e| DingTalkError::InvalidConfig(format!("create blocking HTTP client failed: {}", e)))?;
+        self.dingtalk.blocking_client = Some(client);
+        Ok(())
+    }
+
+    /// Build `DingTalk` without creating HTTP client, never fails, `base_host`, `proxy`, `use_system_proxy`, `with_header` and `with_content_type` are ignored
+    pub(crate) fn build_without_client(self) -> DingTalk {
+        let mut dingtalk = self.dingtalk;
+        if dingtalk.default_webhook_url.is_empty() && dingtalk.direct_url.is_empty() {
+            dingtalk.default_webhook_url = default_webhook_url(dingtalk.dingtalk_type).into();
+        }
+        dingtalk
+    }
+}
+
+/// Parse `Content-Type`, `application/json` or `application/*+json`, with optional parameters(e.g. `charset`)
+fn json_content_type(content_type: &str) -> XResult<HeaderValue> {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let is_json = match media_type.strip_prefix("application/") {
+        Some(subtype) => subtype == "json" || (subtype.ends_with("+json") && subtype.len() > "+json".len()),
+        None => false,
+    };
+    if !is_json {
+        return Err(DingTalkError::InvalidConfig(format!("Content-Type is not JSON media type: {}", content_type)));
+    }
+    HeaderValue::from_str(content_type)
+        .map_err(|_| DingTalkError::InvalidConfig(format!("invalid Content-Type: {}", content_type.escape_debug())))
+}
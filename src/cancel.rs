@@ -0,0 +1,90 @@
+use std::{ collections::HashMap, fmt, future::Future, pin::Pin, sync::{ Arc, Mutex, atomic::{ AtomicBool, AtomicU64, Ordering } }, task::{ Context, Poll, Waker } };
+
+/// Cancellation token, requires feature `cancellation`
+///
+/// Cloned tokens share the state, cancel any of them to cancel all sends using it, e.g. on shutdown.
+/// Runtime independent, only `std` and `futures`.
+///
+/// ```ignore
+/// let cancellation_token = CancellationToken::new();
+/// let dt = DingTalk::builder().access_token("<token>").cancellation_token(cancellation_token.clone()).build()?;
+/// // on shutdown
+/// cancellation_token.cancel();
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    next_id: AtomicU64,
+    // wakers of pending `cancelled` futures, removed when the future is dropped
+    wakers: Mutex<HashMap<u64, Waker>>,
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken").field("is_cancelled", &self.is_cancelled()).finish()
+    }
+}
+
+impl CancellationToken {
+
+    /// Create `CancellationToken`, not cancelled
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancel, wakes all futures waiting on `cancelled`, cancelling again has no effect
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers: Vec<Waker> = self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner())
+            .drain().map(|(_, waker)| waker).collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Is cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Future completes when the token is cancelled
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            id: self.inner.next_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    id: u64,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut wakers = self.token.inner.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        // checked while holding lock, `cancel` sets flag before taking wakers
+        if self.token.is_cancelled() {
+            wakers.remove(&self.id);
+            return Poll::Ready(());
+        }
+        wakers.insert(self.id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        self.token.inner.wakers.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
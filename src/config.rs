@@ -0,0 +1,434 @@
+use std::{ fs, env, io::Write, path::PathBuf, time::Duration };
+use serde::{ Serialize, Deserialize };
+use serde_json::Value;
+use crate::{ DingTalk, DingTalkBuilder, DingTalkError, DingTalkType, DingTalkWebhookParams, XResult };
+
+const DEFAULT_DINGTALK_ROBOT_URL: &str = "https://oapi.dingtalk.com/robot/send";
+const DEFAULT_WECHAT_WORK_ROBOT_URL: &str = "https://qyapi.weixin.qq.com/cgi-bin/webhook/send";
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 500;
+const DEFAULT_ENV_PREFIX: &str = "DINGTALK";
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// `DingTalk` config, can be (de)serialized with serde, all fields are optional
+/// 
+/// ```json
+/// {
+///     "type": "dingtalk", // option, dingtalk or wechat
+///     "default_webhook_url": "", // option
+///     "access_token": "<access token>",
+///     "sec_token": "<sec token>", // option
+///     "direct_url": "", // option
+///     "webhook_params": { "token": "access_token", "timestamp": "timestamp", "sign": "sign" }, // option
+///     "rate_limit_per_minute": 20, // option
+///     "max_retries": 3, // option
+///     "retry_base_delay_ms": 500, // option, default 500
+///     "timeout_ms": 10000, // option
+///     "proxy": "http://proxy.example.com:8080" // option, not available in wasm
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DingTalkConfig {
+    #[serde(rename = "type")]
+    pub dingtalk_type: DingTalkType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_webhook_url: Option<String>,
+    pub access_token: String,
+    pub sec_token: String,
+    pub direct_url: String,
+    pub webhook_params: DingTalkWebhookParams,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_base_delay_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+impl DingTalk {
+
+    /// Create `DingTalk` from `DingTalkConfig`
+    /// 
+    /// Empty `access_token` without `direct_url` is `DingTalkError::InvalidConfig("missing access_token")`
+    pub fn from_config(config: DingTalkConfig) -> XResult<Self> {
+        let mut builder = DingTalk::builder()
+            .dingtalk_type(config.dingtalk_type)
+            .access_token(&config.access_token)
+            .sec_token(&config.sec_token)
+            .direct_url(&config.direct_url)
+            .webhook_url(config.default_webhook_url.as_deref().unwrap_or(default_webhook_url(config.dingtalk_type)))
+            .webhook_params(config.webhook_params);
+        if let Some(timeout_ms) = config.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout_ms));
+        }
+        if let Some(per_minute) = config.rate_limit_per_minute {
+            builder = builder.rate_limit(per_minute);
+        }
+        if let Some(max_retries) = config.max_retries {
+            let retry_base_delay = Duration::from_millis(config.retry_base_delay_ms.unwrap_or(DEFAULT_RETRY_BASE_DELAY_MS));
+            // in wasm `build` rejects retries without sleep, which config can not set
+            builder = builder.retries(max_retries, retry_base_delay);
+        }
+        if let Some(proxy) = &config.proxy {
+            builder = with_config_proxy(builder, proxy)?;
+        }
+        builder.build()
+    }
+
+    /// Create `DingTalk` from token:
+    /// wechatwork:access_token
+    /// dingtalk:access_token?sec_token
+    pub fn from_token(token: &str) -> XResult<Self> {
+        if let Some(token_and_or_sec) = token.strip_prefix("dingtalk:") {
+            let mut token_and_or_sec_vec = token_and_or_sec.split('?');
+            let access_token = match token_and_or_sec_vec.next() {
+                Some(t) => t, None => token_and_or_sec,
+            };
+            let sec_token = token_and_or_sec_vec.next().unwrap_or_default();
+            Ok(Self::new(access_token, sec_token))
+        } else if let Some(key) = token.strip_prefix("wechatwork:") {
+            Ok(Self::new_wechat(key))
+        } else if let Some(key) = token.strip_prefix("wecom:") {
+            Ok(Self::new_wechat(key))
+        } else {
+            Err(DingTalkError::InvalidConfig(format!("token format error: {}", token)))
+        }
+    }
+
+    /// Create `DingTalk` from file
+    /// 
+    /// Format see `DingTalk::from_json(json: &str)`,
+    /// with feature `toml` `*.toml` is TOML, with feature `yaml` `*.yaml` and `*.yml` are YAML
+    /// 
+    /// UTF-8 BOM is skipped, not UTF-8 or invalid config is `DingTalkError::InvalidConfig` with the file path
+    pub fn from_file(f: &str) -> XResult<Self> {
+        let extension = expand_path(f)?.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+        match extension.as_str() {
+            #[cfg(feature = "toml")]
+            "toml" => Self::from_config_file(f, Self::from_toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Self::from_config_file(f, Self::from_yaml),
+            _ => Self::from_config_file(f, Self::from_json),
+        }
+    }
+
+    /// Read config file and parse by `parse`, config errors are `DingTalkError::InvalidConfig` with the file path
+    fn from_config_file(f: &str, parse: fn(&str) -> XResult<Self>) -> XResult<Self> {
+        let path = expand_path(f)?;
+        let f_bytes = fs::read(&path)?;
+        let f_bytes = f_bytes.strip_prefix(UTF8_BOM).unwrap_or(&f_bytes);
+        let f_content = std::str::from_utf8(f_bytes).map_err(|e| DingTalkError::InvalidConfig(
+            format!("config file {} is not UTF-8: {}", path.display(), e)
+        ))?;
+        parse(f_content).map_err(|e| match e {
+            // message of not object error has the content, which has tokens
+            DingTalkError::InvalidConfig(message) if message.starts_with("JSON is not object") => DingTalkError::InvalidConfig(
+                format!("config file {} is not JSON object", path.display())),
+            DingTalkError::InvalidConfig(message) => DingTalkError::InvalidConfig(
+                format!("config file {}: {}", path.display(), message)),
+            DingTalkError::Json(e) => DingTalkError::InvalidConfig(
+                format!("config file {} is not valid JSON: {}", path.display(), e)),
+            e => e,
+        })
+    }
+
+    /// Create `DingTalk` from TOML file, fields are the same as `DingTalkConfig`
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file(f: &str) -> XResult<Self> {
+        Self::from_config_file(f, Self::from_toml)
+    }
+
+    /// Create `DingTalk` from TOML string, fields are the same as `DingTalkConfig`
+    /// 
+    /// ```toml
+    /// access_token = "<access token>"
+    /// sec_token = "<sec token>"
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> XResult<Self> {
+        let config: DingTalkConfig = toml::from_str(toml)
+            .map_err(|e| DingTalkError::InvalidConfig(format!("parse TOML config failed: {}", e)))?;
+        Self::from_config(config)
+    }
+
+    /// Create `DingTalk` from YAML file, fields are the same as `DingTalkConfig`
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_file(f: &str) -> XResult<Self> {
+        Self::from_config_file(f, Self::from_yaml)
+    }
+
+    /// Create `DingTalk` from YAML string, fields are the same as `DingTalkConfig`
+    /// 
+    /// ```yaml
+    /// access_token: <access token>
+    /// sec_token: <sec token>
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> XResult<Self> {
+        let config: DingTalkConfig = serde_yaml::from_str(yaml)
+            .map_err(|e| DingTalkError::InvalidConfig(format!("parse YAML config failed: {}", e)))?;
+        Self::from_config(config)
+    }
+
+    /// Create `DingTalk` from JSON string, fields are the same as `DingTalkConfig`
+    /// 
+    /// Format:
+    /// ```json
+    /// {
+    ///     "default_webhook_url": "", // option
+    ///     "access_token": "<access token>",
+    ///     "sec_token": "<sec token>", // option
+    ///     "proxy": "http://proxy.example.com:8080" // option, not available in wasm
+    /// }
+    /// ```
+    /// 
+    /// Field with wrong JSON type is `DingTalkError::InvalidConfig` with the field name, `null` is absent.
+    /// Empty `access_token` without `direct_url` is `DingTalkError::InvalidConfig("missing access_token")`
+    pub fn from_json(json: &str) -> XResult<Self> {
+        let mut json_value: Value = serde_json::from_str(json)?;
+        let json_object = match json_value.as_object_mut() {
+            Some(json_object) => json_object,
+            None => return Err(DingTalkError::InvalidConfig(format!("JSON is not object: {}", json))),
+        };
+        // `null` is absent, same as the fields not in config
+        json_object.retain(|_, v| !v.is_null());
+        for field in ["type", "default_webhook_url", "access_token", "sec_token", "direct_url", "proxy"].iter() {
+            get_str_field(&json_value, field)?;
+        }
+        for field in ["rate_limit_per_minute", "max_retries", "retry_base_delay_ms", "timeout_ms"].iter() {
+            get_u64_field(&json_value, field)?;
+        }
+        let config: DingTalkConfig = serde_json::from_value(json_value)
+            .map_err(|e| DingTalkError::InvalidConfig(format!("parse JSON config failed: {}", e)))?;
+        Self::from_config(config)
+    }
+
+    /// JSON config, format of `DingTalk::from_json`, includes `access_token` and `sec_token`
+    /// 
+    /// Proxy is written only when set by `DingTalkBuilder::with_proxy`, rate limit and retries are not written
+    pub fn to_json(&self) -> String {
+        let config = DingTalkConfig {
+            dingtalk_type: self.dingtalk_type,
+            default_webhook_url: Some(self.default_webhook_url.clone()),
+            access_token: self.access_token.clone(),
+            sec_token: self.sec_token.clone(),
+            direct_url: self.direct_url.clone(),
+            webhook_params: self.webhook_params.clone(),
+            timeout_ms: self.timeout.map(|timeout| timeout.as_millis() as u64),
+            proxy: self.proxy_url.clone(),
+            ..Default::default()
+        };
+        serde_json::to_string_pretty(&config).unwrap_or_default()
+    }
+
+    /// Write JSON config to file, supports `~/`, read it back by `DingTalk::from_file`
+    /// 
+    /// On Unix a new file is created with mode `0600`, the config has tokens
+    pub fn to_file(&self, f: &str) -> XResult<()> {
+        let mut open_options = fs::OpenOptions::new();
+        open_options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut open_options, 0o600);
+        let mut file = open_options.open(expand_path(f)?)?;
+        file.write_all(self.to_json().as_bytes())?;
+        Ok(())
+    }
+
+    /// Create `DingTalk` from environment variables
+    /// 
+    /// * `DINGTALK_ACCESS_TOKEN` - required
+    /// * `DINGTALK_SEC_TOKEN` - option
+    /// * `DINGTALK_WEBHOOK_URL` - option
+    /// * `DINGTALK_PROXY` - option, HTTP proxy URL
+    /// 
+    /// HTTP client uses proxy from `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+    pub fn from_env() -> XResult<Self> {
+        Self::from_env_prefixed(DEFAULT_ENV_PREFIX)
+    }
+
+    /// Create `DingTalk` from environment variables with `prefix`, e.g. `<prefix>_ACCESS_TOKEN`
+    pub fn from_env_prefixed(prefix: &str) -> XResult<Self> {
+        let access_token = get_env(prefix, "ACCESS_TOKEN")?.ok_or_else(|| DingTalkError::InvalidConfig(
+            format!("environment variable {}_ACCESS_TOKEN is not set", prefix)
+        ))?;
+        let sec_token = get_env(prefix, "SEC_TOKEN")?.unwrap_or_default();
+        let webhook_url = get_env(prefix, "WEBHOOK_URL")?.unwrap_or_default();
+        let mut builder = DingTalk::builder()
+            .access_token(&access_token)
+            .sec_token(&sec_token)
+            .webhook_url(&webhook_url);
+        if let Some(proxy) = get_env(prefix, "PROXY")? {
+            builder = with_config_proxy(builder, &proxy)?;
+        }
+        builder.build()
+    }
+
+    /// Create `DingTalk` from url, for outgoing robot
+    /// 
+    /// Transport options apply to direct URL too, e.g. `DingTalk::from_url(url).with_timeout(timeout)`,
+    /// or `DingTalk::builder().direct_url(url).with_proxy(proxy_url).build()`
+    pub fn from_url(direct_url: &str) -> Self {
+        DingTalk::builder().direct_url(direct_url).build_without_client()
+    }
+
+    /// Create `DingTalk`
+    /// `access_token` is access token, `sec_token` can be empty `""`
+    pub fn new(access_token: &str, sec_token: &str) -> Self {
+        DingTalk::builder().access_token(access_token).sec_token(sec_token).build_without_client()
+    }
+
+    /// Create `DingTalk` for WeChat Work
+    pub fn new_wechat(key: &str) -> Self {
+        DingTalk::builder().dingtalk_type(DingTalkType::WeChatWork).access_token(key).build_without_client()
+    }
+
+    /// Set default webhook url
+    pub fn set_default_webhook_url(&mut self, default_webhook_url: &str) {
+        self.default_webhook_url = default_webhook_url.into();
+    }
+
+    /// Access token(key of WeChat Work)
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// Sec token, SENSITIVE, anyone with it and access token can send messages, never log it
+    pub fn sec_token(&self) -> &str {
+        &self.sec_token
+    }
+
+    /// Is signing enabled, `sec_token` is not empty
+    pub fn has_sec_token(&self) -> bool {
+        !self.sec_token.is_empty()
+    }
+
+    /// Webhook URL, `direct_url` when set, otherwise `default_webhook_url`, without token and sign
+    pub fn webhook_url(&self) -> &str {
+        if self.direct_url.is_empty() { &self.default_webhook_url } else { &self.direct_url }
+    }
+
+    /// Set access token, e.g. reload credentials without restart
+    pub fn set_access_token(&mut self, access_token: &str) {
+        self.access_token = access_token.into();
+    }
+
+    /// Set sec token, e.g. rotate a leaked secret without restart
+    pub fn set_sec_token(&mut self, sec_token: &str) {
+        self.sec_token = sec_token.into();
+    }
+}
+
+/// Set proxy from config, proxy is not available in wasm
+#[cfg(not(target_arch = "wasm32"))]
+fn with_config_proxy(builder: DingTalkBuilder, proxy: &str) -> XResult<DingTalkBuilder> {
+    Ok(builder.with_proxy(proxy))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn with_config_proxy(_builder: DingTalkBuilder, _proxy: &str) -> XResult<DingTalkBuilder> {
+    Err(DingTalkError::InvalidConfig("proxy is not available in wasm".into()))
+}
+
+/// Get environment variable `<prefix>_<name>`, not set or empty is `None`
+fn get_env(prefix: &str, name: &str) -> XResult<Option<String>> {
+    let key = format!("{}_{}", prefix, name);
+    match env::var(&key) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(DingTalkError::InvalidConfig(format!("environment variable {}: {}", key, e))),
+    }
+}
+
+/// Expand `~` and `~/` to home dir, `HOME` or the system home dir when `HOME` is not set
+/// 
+/// `~user/` is not supported, returns `DingTalkError::InvalidConfig` instead of a confusing file not found
+pub(crate) fn expand_path(f: &str) -> XResult<PathBuf> {
+    let f_in_home = match f.strip_prefix('~') {
+        None => return Ok(PathBuf::from(f)),
+        Some("") => "",
+        Some(f_in_home) => match f_in_home.strip_prefix('/') {
+            Some(f_in_home) => f_in_home,
+            None => return Err(DingTalkError::InvalidConfig(format!("~user path is not supported: {}", f))),
+        },
+    };
+    let home = match env::var_os("HOME") {
+        Some(home) if !home.is_empty() => PathBuf::from(home),
+        _ => dirs::home_dir().ok_or_else(|| DingTalkError::InvalidConfig(format!("get home dir failed: {}", f)))?,
+    };
+    Ok(home.join(f_in_home))
+}
+
+/// Read UTF-8 text file, not UTF-8 is `InvalidContent` error
+pub(crate) fn read_text_file(f: &str) -> XResult<String> {
+    let f_bytes = fs::read(expand_path(f)?)?;
+    match String::from_utf8(f_bytes) {
+        Ok(f_content) => Ok(f_content),
+        Err(e) => Err(DingTalkError::InvalidContent(format!("file {} is not valid UTF-8: {}", f, e))),
+    }
+}
+
+/// Default webhook url of `DingTalkType`
+pub(crate) fn default_webhook_url(dingtalk_type: DingTalkType) -> &'static str {
+    match dingtalk_type {
+        DingTalkType::DingTalk => DEFAULT_DINGTALK_ROBOT_URL,
+        DingTalkType::WeChatWork => DEFAULT_WECHAT_WORK_ROBOT_URL,
+    }
+}
+
+/// Robot path of `DingTalkType`, path of default webhook url
+fn robot_path(dingtalk_type: DingTalkType) -> &'static str {
+    match dingtalk_type {
+        DingTalkType::DingTalk => "/robot/send",
+        DingTalkType::WeChatWork => "/cgi-bin/webhook/send",
+    }
+}
+
+/// Webhook url of `DingTalkType` on `base_host`, e.g. `https://oapi.example.com` is `https://oapi.example.com/robot/send`
+/// 
+/// `base_host` without scheme is https, other schemes, path and query are `InvalidConfig` error
+pub(crate) fn base_host_webhook_url(dingtalk_type: DingTalkType, base_host: &str) -> XResult<String> {
+    let base_url = if base_host.contains("://") { base_host.to_owned() } else { format!("https://{}", base_host) };
+    let url = reqwest::Url::parse(&base_url)
+        .map_err(|e| DingTalkError::InvalidConfig(format!("invalid base host {}: {}", base_host, e)))?;
+    if url.scheme() != "https" {
+        return Err(DingTalkError::InvalidConfig(format!("base host must be https: {}", base_host)));
+    }
+    if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+        return Err(DingTalkError::InvalidConfig(format!("base host must not have path or query: {}", base_host)));
+    }
+    Ok(format!("{}{}", url.as_str().trim_end_matches('/'), robot_path(dingtalk_type)))
+}
+
+/// Get unsigned integer field, absent or `null` is `None`, other JSON type is `InvalidConfig` error
+fn get_u64_field(json_value: &Value, field: &str) -> XResult<Option<u64>> {
+    let type_name = match &json_value[field] {
+        Value::Null => return Ok(None),
+        Value::Number(n) => match n.as_u64() {
+            Some(n) => return Ok(Some(n)),
+            None => "number which is not unsigned integer",
+        },
+        Value::Bool(_) => "bool",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    Err(DingTalkError::InvalidConfig(format!("field `{}` expected unsigned integer, got {}", field, type_name)))
+}
+
+/// Get string field, absent or `null` is `None`, other JSON type is `InvalidConfig` error
+fn get_str_field<'a>(json_value: &'a Value, field: &str) -> XResult<Option<&'a str>> {
+    let type_name = match &json_value[field] {
+        Value::Null => return Ok(None),
+        Value::String(s) => return Ok(Some(s)),
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    Err(DingTalkError::InvalidConfig(format!("field `{}` expected string, got {}", field, type_name)))
+}
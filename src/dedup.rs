@@ -0,0 +1,74 @@
+use std::{ collections::{ hash_map::DefaultHasher, VecDeque }, hash::{ Hash, Hasher }, sync::Mutex, time::{ Duration, Instant } };
+
+/// Default max entries of `Deduplicator`
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Suppresses duplicate messages, e.g. alert fired repeatedly by monitoring system
+/// 
+/// A message is duplicate when the identical JSON message was sent successfully to the same robot within `window`.
+/// Only hashes of robot and JSON message are kept, at most `max_entries`, the oldest are evicted first.
+/// `DingTalk` shares the deduplicator with `Arc`, so cloned `DingTalk` suppresses the same messages.
+#[derive(Debug)]
+pub struct Deduplicator {
+    window: Duration,
+    max_entries: usize,
+    // (hash of robot and JSON message, sent at), oldest first
+    sent: Mutex<VecDeque<(u64, Instant)>>,
+}
+
+impl Deduplicator {
+
+    /// Create `Deduplicator`, suppresses message sent within `window`, not available in wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(window: Duration) -> Self {
+        Deduplicator {
+            window,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Set max entries, default 1024
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Is `json_message` sent to `robot` within window,
+    /// `robot` identifies the target, e.g. webhook URL and access token, so one deduplicator can be shared between robots
+    pub fn is_duplicate(&self, robot: &str, json_message: &str) -> bool {
+        let hash = hash_message(robot, json_message);
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_expired(&mut sent);
+        sent.iter().any(|(h, _)| *h == hash)
+    }
+
+    /// Record `json_message` is sent to `robot`
+    pub fn record(&self, robot: &str, json_message: &str) {
+        let hash = hash_message(robot, json_message);
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_expired(&mut sent);
+        sent.retain(|(h, _)| *h != hash);
+        while sent.len() >= self.max_entries {
+            sent.pop_front();
+        }
+        sent.push_back((hash, Instant::now()));
+    }
+
+    fn evict_expired(&self, sent: &mut VecDeque<(u64, Instant)>) {
+        while let Some((_, sent_at)) = sent.front() {
+            if sent_at.elapsed() < self.window {
+                break;
+            }
+            sent.pop_front();
+        }
+    }
+}
+
+fn hash_message(robot: &str, json_message: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    robot.hash(&mut hasher);
+    json_message.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,107 @@
+use std::{ fmt, io, time::Duration };
+use crate::sign::redact_url;
+
+/// DingTalk error
+#[derive(Debug)]
+pub enum DingTalkError {
+    /// HTTP error, e.g. connect failed
+    Http(reqwest::Error),
+    /// Request timeout, see `DingTalkBuilder::timeout`
+    Timeout,
+    /// Send is cancelled, see `DingTalkBuilder::cancellation_token`(feature `cancellation`)
+    Cancelled,
+    /// Unexpected HTTP status
+    Status(u16),
+    /// DingTalk API error, response `errcode` is not 0
+    Api { code: i64, message: String },
+    /// Robot requires signature but `sec_token` is not configured, with DingTalk `errmsg`
+    SignatureRequired(String),
+    /// Invalid config, e.g. config field has wrong type
+    InvalidConfig(String),
+    /// Invalid message, e.g. empty text, content too large, see `DingTalkMessage::validate`
+    InvalidMessage(String),
+    /// Invalid message content, e.g. file is not valid UTF-8
+    InvalidContent(String),
+    /// Rate limited(HTTP 429 or errcode 130101), `retry_after` is from `Retry-After` header, 60 seconds of errcode 130101
+    RateLimited { retry_after: Option<Duration> },
+    /// Unexpected response, e.g. `errcode` is not a number
+    UnexpectedResponse(String),
+    /// Clock error, e.g. system time is before UNIX epoch, request is not signed with a wrong timestamp
+    Clock(String),
+    /// JSON error
+    Json(serde_json::Error),
+    /// IO error, e.g. read config file failed
+    Io(io::Error),
+}
+
+impl fmt::Display for DingTalkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DingTalkError::Http(e) => {
+                // reqwest error message has the signed URL, with token and sign
+                let message = match e.url() {
+                    Some(url) => e.to_string().replace(url.as_str(), &redact_url(url)),
+                    None => e.to_string(),
+                };
+                write!(f, "HTTP error: {}", message)
+            },
+            DingTalkError::Timeout => write!(f, "Request timeout"),
+            DingTalkError::Cancelled => write!(f, "Send cancelled"),
+            DingTalkError::Status(status) => write!(f, "Unknown status: {}", status),
+            DingTalkError::Api { code, message } => write!(f, "DingTalk API error, errcode: {}, errmsg: {}", code, message),
+            DingTalkError::SignatureRequired(errmsg) => write!(f,
+                "Robot requires signature, please configure `sec_token`: {}", errmsg),
+            DingTalkError::InvalidConfig(message) => write!(f, "Invalid config: {}", message),
+            DingTalkError::InvalidMessage(message) => write!(f, "Invalid message: {}", message),
+            DingTalkError::InvalidContent(message) => write!(f, "Invalid content: {}", message),
+            DingTalkError::RateLimited { retry_after: Some(retry_after) } => write!(f,
+                "Rate limited, retry after {} seconds", retry_after.as_secs()),
+            DingTalkError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            DingTalkError::UnexpectedResponse(message) => write!(f, "Unexpected response: {}", message),
+            DingTalkError::Clock(message) => write!(f, "Clock error: {}", message),
+            DingTalkError::Json(e) => write!(f, "JSON error: {}", e),
+            DingTalkError::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+impl DingTalkError {
+
+    /// Is retryable: connection errors, timeouts, HTTP 429 and 5xx
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            DingTalkError::Http(e) => e.is_connect(),
+            // browser fetch does not tell connection errors apart
+            DingTalkError::Timeout => true,
+            DingTalkError::Status(status) => *status >= 500,
+            DingTalkError::RateLimited { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for DingTalkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DingTalkError::Http(e) => Some(e),
+            DingTalkError::Json(e) => Some(e),
+            DingTalkError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for DingTalkError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() { DingTalkError::Timeout } else { DingTalkError::Http(e) }
+    }
+}
+
+impl From<serde_json::Error> for DingTalkError {
+    fn from(e: serde_json::Error) -> Self { DingTalkError::Json(e) }
+}
+
+impl From<io::Error> for DingTalkError {
+    fn from(e: io::Error) -> Self { DingTalkError::Io(e) }
+}
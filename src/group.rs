@@ -0,0 +1,36 @@
+use futures::future::join_all;
+use crate::{ DingTalk, DingTalkMessage, XResult };
+
+/// Group of robots, broadcasts one message to all robots, e.g. dev channel and on-call channel
+/// 
+/// ```ignore
+/// let group = DingTalkGroup::new(vec![dev_dt, oncall_dt]);
+/// for result in group.broadcast(&DingTalkMessage::new_text("disk full")).await {
+///     result?;
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct DingTalkGroup {
+    pub robots: Vec<DingTalk>,
+}
+
+impl DingTalkGroup {
+
+    /// Create `DingTalkGroup`
+    pub fn new(robots: Vec<DingTalk>) -> Self {
+        DingTalkGroup { robots }
+    }
+
+    /// Add robot
+    pub fn add_robot(mut self, dingtalk: DingTalk) -> Self {
+        self.robots.push(dingtalk);
+        self
+    }
+
+    /// Send message to all robots concurrently, results are in order of `robots`
+    /// 
+    /// A failed robot does not stop sending to the others, each robot uses its own retry and dead letter.
+    pub async fn broadcast(&self, dingtalk_message: &DingTalkMessage) -> Vec<XResult<()>> {
+        join_all(self.robots.iter().map(|dt| dt.send_message(dingtalk_message.clone()))).await
+    }
+}
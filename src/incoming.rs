@@ -0,0 +1,73 @@
+use serde::{ Serialize, Deserialize };
+use crate::XResult;
+
+/// Incoming message text
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IncomingMessageText {
+    pub content: String,
+}
+
+/// User @ in incoming message
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IncomingMessageAtUser {
+    pub dingtalk_id: String,
+    pub staff_id: Option<String>,
+}
+
+/// Incoming message, the JSON DingTalk posts to outgoing robot
+///
+/// Absent fields are default, unknown fields are ignored.
+/// Verify request with `verify_signature` first, reply to `session_webhook` with `DingTalk::from_url`.
+///
+/// ```ignore
+/// let incoming = IncomingMessage::parse(body)?;
+/// let dt = DingTalk::from_url(&incoming.session_webhook);
+/// dt.send_text(&format!("Hi {}", incoming.sender_nick)).await?;
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IncomingMessage {
+    #[serde(rename = "msgtype")]
+    pub msg_type: String,
+    pub text: IncomingMessageText,
+    pub msg_id: String,
+    /// Millis since epoch
+    pub create_at: i64,
+    /// "1" single chat, "2" group chat
+    pub conversation_type: String,
+    pub conversation_id: String,
+    pub conversation_title: Option<String>,
+    pub sender_id: String,
+    pub sender_nick: String,
+    pub sender_corp_id: Option<String>,
+    pub sender_staff_id: Option<String>,
+    pub chatbot_user_id: String,
+    pub at_users: Vec<IncomingMessageAtUser>,
+    pub is_admin: bool,
+    pub is_in_at_list: bool,
+    /// Webhook to reply this conversation, valid until `session_webhook_expired_time`
+    pub session_webhook: String,
+    /// Millis since epoch
+    pub session_webhook_expired_time: i64,
+}
+
+impl IncomingMessage {
+
+    /// Parse incoming message JSON
+    pub fn parse(body: &str) -> XResult<Self> {
+        Ok(serde_json::from_str(body)?)
+    }
+
+    /// Text content with leading and trailing whitespaces trimmed,
+    /// DingTalk prefixes content with a space when robot is @
+    pub fn text_content(&self) -> &str {
+        self.text.content.trim()
+    }
+
+    /// Is group chat
+    pub fn is_group(&self) -> bool {
+        self.conversation_type == "2"
+    }
+}
@@ -0,0 +1,125 @@
+/// Markdown builder, for DingTalk markdown message
+/// 
+/// DingTalk renders only a subset of markdown, and blocks must be separated by an empty line,
+/// `MarkdownBuilder` inserts the newlines between blocks.
+/// 
+/// ```ignore
+/// let markdown = MarkdownBuilder::new()
+///     .heading(3, "Build failed")
+///     .bold("dingtalk")
+///     .list_item("branch: master")
+///     .list_item("commit: 9a7d431")
+///     .link("Details", "https://example.com/build/1")
+///     .build();
+/// dt.send_message(DingTalkMessage::new_markdown("Build failed", &markdown)).await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MarkdownBuilder {
+    blocks: Vec<MarkdownBlock>,
+}
+
+#[derive(Clone, Debug)]
+enum MarkdownBlock {
+    Paragraph(String),
+    ListItem(String),
+}
+
+impl MarkdownBuilder {
+
+    /// Create `MarkdownBuilder`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Heading, `level` is 1 to 6
+    pub fn heading(self, level: u8, text: &str) -> Self {
+        let level = level.clamp(1, 6) as usize;
+        self.paragraph(format!("{} {}", "#".repeat(level), text))
+    }
+
+    /// Text paragraph
+    pub fn text(self, text: &str) -> Self {
+        self.paragraph(text.into())
+    }
+
+    /// Text paragraph, `text` is escaped by `escape_markdown`, e.g. log lines and error messages
+    pub fn text_escaped(self, text: &str) -> Self {
+        self.paragraph(escape_markdown(text))
+    }
+
+    /// Bold paragraph
+    pub fn bold(self, text: &str) -> Self {
+        self.paragraph(format!("**{}**", text))
+    }
+
+    /// Quote paragraph
+    pub fn quote(self, text: &str) -> Self {
+        self.paragraph(format!("> {}", text))
+    }
+
+    /// Link paragraph
+    pub fn link(self, text: &str, url: &str) -> Self {
+        self.paragraph(format!("[{}]({})", text, url))
+    }
+
+    /// Image paragraph, `url` must be https, DingTalk does not show http images,
+    /// see `DingTalkMessage::validate`
+    pub fn image(self, alt: &str, url: &str) -> Self {
+        self.paragraph(format!("![{}]({})", alt, url))
+    }
+
+    /// At mobile paragraph, the mobile must also be in `DingTalkMessage::at_mobiles`
+    pub fn at_mobile(self, mobile: &str) -> Self {
+        self.paragraph(format!("@{}", mobile))
+    }
+
+    /// List item, consecutive list items are one list
+    pub fn list_item(mut self, text: &str) -> Self {
+        self.blocks.push(MarkdownBlock::ListItem(format!("- {}", text)));
+        self
+    }
+
+    /// Build markdown content
+    pub fn build(&self) -> String {
+        let mut markdown = String::new();
+        let mut last_is_list_item = false;
+        for (i, block) in self.blocks.iter().enumerate() {
+            let (content, is_list_item) = match block {
+                MarkdownBlock::Paragraph(p) => (p, false),
+                MarkdownBlock::ListItem(l) => (l, true),
+            };
+            if i > 0 {
+                markdown.push_str(if last_is_list_item && is_list_item { "\n" } else { "\n\n" });
+            }
+            markdown.push_str(content);
+            last_is_list_item = is_list_item;
+        }
+        markdown
+    }
+
+    fn paragraph(mut self, paragraph: String) -> Self {
+        self.blocks.push(MarkdownBlock::Paragraph(paragraph));
+        self
+    }
+}
+
+/// Markdown significant characters, escaped by `escape_markdown`
+const MARKDOWN_SPECIAL_CHARS: &str = "\\`*_{}[]()#+-!|<>~";
+
+/// Escape markdown, inserted text is shown as is
+/// 
+/// Rules:
+/// * `\` `` ` `` `*` `_` `{` `}` `[` `]` `(` `)` `#` `+` `-` `!` `|` `<` `>` `~` are prefixed with `\`,
+///   anywhere in the text, not only at line start
+/// * `.` is not escaped, a line starting with e.g. `1. ` may still render as ordered list
+/// * Newlines and other characters are unchanged
+pub fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + s.len() / 8);
+    for c in s.chars() {
+        if MARKDOWN_SPECIAL_CHARS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,819 @@
+use std::{ fmt, str::FromStr };
+use serde::{ Serialize, Deserialize };
+use serde_json::Value;
+use crate::{ DingTalkError, MarkdownBuilder, XResult };
+
+/// Max bytes of text and markdown content
+const TEXT_CONTENT_MAX_BYTES: usize = 20000;
+/// Max bytes of markdown title, title is shown in notification and conversation list, a CJK char is 3 bytes
+const MARKDOWN_TITLE_MAX_BYTES: usize = 128;
+/// Max bytes of numbering prefix of text chunks, e.g. `(1/3) `
+const TEXT_CHUNK_PREFIX_MAX_BYTES: usize = 32;
+/// Max bytes of action card text, action card is rendered in a card so the limit is smaller
+const ACTION_CARD_TEXT_MAX_BYTES: usize = 5000;
+/// Max independent btns of action card, more btns are dropped or rejected by DingTalk
+const ACTION_CARD_MAX_BTNS: usize = 5;
+
+/// Send Dingtalk or WeChatWork message
+/// 
+/// Deserialized by `FromStr`, case insensitive `dingtalk`, `wechat`, `wechatwork` or `wecom`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum DingTalkType {
+    /// DingTalk
+    #[serde(rename = "dingtalk")]
+    DingTalk,
+    /// WeChatWork
+    #[serde(rename = "wechat")]
+    WeChatWork,
+}
+
+/// Default DingTalkType is DingTalk
+impl Default for DingTalkType {
+    fn default() -> Self { DingTalkType::DingTalk }
+}
+
+impl FromStr for DingTalkType {
+    type Err = DingTalkError;
+
+    fn from_str(s: &str) -> XResult<Self> {
+        match s.to_lowercase().as_str() {
+            "dingtalk" => Ok(DingTalkType::DingTalk),
+            "wechat" | "wechatwork" | "wecom" => Ok(DingTalkType::WeChatWork),
+            _ => Err(DingTalkError::InvalidConfig(format!("unknown type: {}", s))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DingTalkType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// DingTalk message type
+/// * Text - text message
+/// * Markdown - markdown message
+/// * Link - link message
+/// * ActionCard - action card message
+/// * FeedCard - feed card message
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DingTalkMessageType {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "markdown")]
+    Markdown,
+    #[serde(rename = "link")]
+    Link,
+    #[serde(rename = "actionCard")]
+    ActionCard,
+    #[serde(rename = "feedCard")]
+    FeedCard,
+}
+
+/// Default DingTalkMessageType is Text
+impl Default for DingTalkMessageType {
+    fn default() -> Self { DingTalkMessageType::Text }
+}
+
+impl DingTalkMessageType {
+
+    /// DingTalk `msgtype`, e.g. `actionCard`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DingTalkMessageType::Text => "text",
+            DingTalkMessageType::Markdown => "markdown",
+            DingTalkMessageType::Link => "link",
+            DingTalkMessageType::ActionCard => "actionCard",
+            DingTalkMessageType::FeedCard => "feedCard",
+        }
+    }
+}
+
+impl fmt::Display for DingTalkMessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse DingTalk `msgtype` case-insensitive, e.g. from config or CLI arguments,
+/// unknown type is `DingTalkError::InvalidConfig`
+impl FromStr for DingTalkMessageType {
+    type Err = DingTalkError;
+
+    fn from_str(s: &str) -> XResult<Self> {
+        let message_types = [
+            DingTalkMessageType::Text,
+            DingTalkMessageType::Markdown,
+            DingTalkMessageType::Link,
+            DingTalkMessageType::ActionCard,
+            DingTalkMessageType::FeedCard,
+        ];
+        message_types.iter().copied().find(|t| t.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| DingTalkError::InvalidConfig(format!("unknown message type: {}", s)))
+    }
+}
+
+/// DingTalk messge action card avatar, default value is Show
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum DingTalkMessageActionCardHideAvatar {
+    #[serde(rename = "1")]
+    Hide,
+    #[serde(rename = "0")]
+    Show,
+}
+
+/// default value
+impl Default for DingTalkMessageActionCardHideAvatar {
+    fn default() -> Self { DingTalkMessageActionCardHideAvatar::Show }
+}
+
+/// DingTalk message action card orientation
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum DingTalkMessageActionCardBtnOrientation {
+    #[serde(rename = "0")]
+    Vertical,
+    #[serde(rename = "1")]
+    Landscape,
+}
+
+/// default value
+impl Default for DingTalkMessageActionCardBtnOrientation {
+    fn default() -> Self { DingTalkMessageActionCardBtnOrientation::Vertical }
+}
+
+/// DingTalk message action card btn
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DingTalkMessageActionCardBtn {
+    pub title: String,
+    pub action_url: String,
+}
+
+/// DingTalk message feed card link
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DingTalkMessageFeedCardLink {
+    pub title: String,
+    pub message_url: String,
+    pub pic_url: String,
+}
+
+/// At block of serialized JSON message, see `DingTalk::send_with_at`
+/// 
+/// Same as `DingTalkMessage`, `mobiles` and `user_ids` are ignored when `at_all`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtConfig {
+    pub at_all: bool,
+    pub mobiles: Vec<String>,
+    pub user_ids: Vec<String>,
+}
+
+impl AtConfig {
+
+    /// DingTalk JSON at block, `None` when nobody is @
+    pub(crate) fn to_json_value(&self) -> Option<Value> {
+        at_json_value(self.at_all, &self.mobiles, &self.user_ids)
+    }
+}
+
+/// DingTalk message
+/// 
+/// Can be (de)serialized with serde, e.g. queued for sending later, absent fields are default.
+/// This is NOT the JSON sent to DingTalk, see `DingTalk::build_message_json`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DingTalkMessage {
+    pub message_type: DingTalkMessageType,
+    pub text_content: String,
+    pub markdown_title: String,
+    pub markdown_content: String,
+    pub link_text: String,
+    pub link_title: String,
+    pub link_pic_url: String,
+    pub link_message_url: String,
+    pub action_card_title: String,
+    pub action_card_text: String,
+    pub action_card_hide_avatar: DingTalkMessageActionCardHideAvatar,
+    pub action_card_btn_orientation: DingTalkMessageActionCardBtnOrientation,
+    pub action_card_single_btn: Option<DingTalkMessageActionCardBtn>,
+    pub action_card_btns: Vec<DingTalkMessageActionCardBtn>,
+    pub feed_card_links: Vec<DingTalkMessageFeedCardLink>,
+    pub at_all: bool,
+    pub at_mobiles: Vec<String>,
+    pub at_user_ids: Vec<String>,
+    /// Extra top level fields of message JSON, e.g. fields added by DingTalk but not supported yet,
+    /// must be a JSON object, overrides built-in fields(e.g. `msgtype`, `at`) on conflict
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_fields: Option<Value>,
+}
+
+/// Only fields of `message_type`, e.g. `Link[title]: https://example.com/`, mentions are appended,
+/// only `@all` when at all, DingTalk ignores other mentions then
+impl fmt::Display for DingTalkMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message_type {
+            DingTalkMessageType::Text => write!(f, "Text: {}", self.text_content)?,
+            DingTalkMessageType::Markdown => write!(f, "Markdown[{}]: {}", self.markdown_title, self.markdown_content)?,
+            DingTalkMessageType::Link => write!(f, "Link[{}]: {}", self.link_title, self.link_message_url)?,
+            DingTalkMessageType::ActionCard => {
+                let btn_count = self.action_card_btns.len() + self.action_card_single_btn.iter().count();
+                write!(f, "ActionCard[{}]: {} ({} btns)", self.action_card_title, self.action_card_text, btn_count)?
+            },
+            DingTalkMessageType::FeedCard => {
+                let titles: Vec<&str> = self.feed_card_links.iter().map(|l| l.title.as_str()).collect();
+                write!(f, "FeedCard: {}", titles.join(", "))?
+            },
+        }
+        if self.at_all {
+            return write!(f, " @all");
+        }
+        for m in self.at_mobiles.iter().chain(self.at_user_ids.iter()) {
+            write!(f, " @{}", m)?;
+        }
+        Ok(())
+    }
+}
+
+impl DingTalkMessage {
+
+    /// New text DingTalk message
+    pub fn new_text(text_content: &str) -> Self {
+        Self::new(DingTalkMessageType::Text).text(text_content)
+    }
+
+    /// New markdown DingTalk message
+    pub fn new_markdown(markdown_title: &str, markdown_content: &str) -> Self {
+        Self::new(DingTalkMessageType::Markdown).markdown(markdown_title, markdown_content)
+    }
+
+    /// New markdown DingTalk message, image is appended after text, `image_url` must be https
+    pub fn new_markdown_with_image(markdown_title: &str, markdown_text: &str, image_url: &str) -> Self {
+        let markdown_content = MarkdownBuilder::new().text(markdown_text).image(markdown_title, image_url).build();
+        Self::new_markdown(markdown_title, &markdown_content)
+    }
+
+    /// New link DingTalk message
+    pub fn new_link(link_title: &str, link_text: &str, link_pic_url: &str, link_message_url: &str) -> Self {
+        Self::new(DingTalkMessageType::Link).link(link_title, link_text, link_pic_url, link_message_url)
+    }
+
+    /// New action card DingTalk message
+    pub fn new_action_card(title: &str, text: &str) -> Self {
+        let mut s = Self::new(DingTalkMessageType::ActionCard);
+        s.action_card_title = title.into();
+        s.action_card_text = text.into();
+        s
+    }
+
+    /// New feed card DingTalk message
+    pub fn new_feed_card() -> Self {
+        Self::new(DingTalkMessageType::FeedCard)
+    }
+    
+    /// New DingTalk message
+    pub fn new(message_type: DingTalkMessageType) -> Self {
+        DingTalkMessage {
+            message_type,
+            ..Default::default()
+        }
+    }
+
+    /// Clone message as template and modify the clone
+    /// 
+    /// ```ignore
+    /// let card = template.clone_with(|m| m.single_button(btn));
+    /// ```
+    pub fn clone_with<F>(&self, f: F) -> Self where F: FnOnce(Self) -> Self {
+        f(self.clone())
+    }
+
+    /// Set text
+    pub fn text(mut self, text_content: &str) -> Self {
+        self.text_content = text_content.into();
+        self
+    }
+
+    /// Set markdown
+    pub fn markdown(mut self, markdown_title: &str, markdown_content: &str) -> Self {
+        self.markdown_title = markdown_title.into();
+        self.markdown_content = markdown_content.into();
+        self
+    }
+
+    /// Set link
+    pub fn link(mut self, link_title: &str, link_text: &str, link_pic_url: &str, link_message_url: &str) -> Self {
+        self.link_title = link_title.into();
+        self.link_text = link_text.into();
+        self.link_pic_url = link_pic_url.into();
+        self.link_message_url = link_message_url.into();
+        self
+    }
+
+    /// Set action card show avator(default show)
+    pub fn action_card_show_avatar(mut self) -> Self {
+        self.action_card_hide_avatar = DingTalkMessageActionCardHideAvatar::Show;
+        self
+    }
+
+    /// Set action card hide avator
+    pub fn action_card_hide_avatar(mut self) -> Self {
+        self.action_card_hide_avatar = DingTalkMessageActionCardHideAvatar::Hide;
+        self
+    }
+
+    /// Set action card btn vertical(default vertical)
+    pub fn action_card_btn_vertical(mut self) -> Self {
+        self.action_card_btn_orientation = DingTalkMessageActionCardBtnOrientation::Vertical;
+        self
+    }
+
+    /// Set action card btn landscape
+    pub fn action_card_btn_landscape(mut self) -> Self {
+        self.action_card_btn_orientation = DingTalkMessageActionCardBtnOrientation::Landscape;
+        self
+    }
+
+    /// Set action card single btn(single jump), single btn and btns(independent jump) are mutually exclusive
+    pub fn single_button(mut self, btn: DingTalkMessageActionCardBtn) -> Self {
+        self.action_card_single_btn = Some(btn);
+        self
+    }
+
+    /// Set action card single btn
+    #[deprecated(since = "3.0.0", note = "use `single_button`")]
+    pub fn set_action_card_signle_btn(self, btn: DingTalkMessageActionCardBtn) -> Self {
+        self.single_button(btn)
+    }
+
+    /// Add action card btn, single btn and btns are mutually exclusive, at most 5 btns
+    pub fn add_action_card_btn(mut self, btn: DingTalkMessageActionCardBtn) -> Self {
+        self.action_card_btns.push(btn);
+        self
+    }
+    
+    /// Add feed card link
+    pub fn add_feed_card_link(mut self, link: DingTalkMessageFeedCardLink) -> Self {
+        self.feed_card_links.push(link);
+        self
+    }
+
+    /// Add feed card link detail
+    pub fn add_feed_card_link_detail(self, title: &str, message_url: &str, pic_url: &str) -> Self {
+        self.add_feed_card_link(DingTalkMessageFeedCardLink {
+            title: title.into(),
+            message_url: message_url.into(),
+            pic_url: pic_url.into(),
+        })
+    }
+
+    /// At all, DingTalk notifies everyone, `at_mobiles` and `at_user_ids` are ignored and not sent
+    pub fn at_all(mut self) -> Self {
+        self.at_all = true;
+        self
+    }
+
+    /// Clear at all, at mobiles and at user ids
+    pub fn clear_at(mut self) -> Self {
+        self.at_all = false;
+        self.at_mobiles.clear();
+        self.at_user_ids.clear();
+        self
+    }
+
+    /// At mobiles, e.g. `["13800000000"]` or `&mobiles`
+    pub fn at_mobiles<I, S>(mut self, mobiles: I) -> Self where I: IntoIterator<Item = S>, S: AsRef<str> {
+        for m in mobiles {
+            self.at_mobiles.push(m.as_ref().into());
+        }
+        self
+    }
+
+    /// At mobile, `.at_mobile("13800000000").at_mobile("13900000000")`
+    pub fn at_mobile(mut self, mobile: &str) -> Self {
+        self.at_mobiles.push(mobile.into());
+        self
+    }
+
+    /// At user ids, DingTalk userid instead of mobile
+    pub fn at_user_ids<I, S>(mut self, user_ids: I) -> Self where I: IntoIterator<Item = S>, S: AsRef<str> {
+        for u in user_ids {
+            self.at_user_ids.push(u.as_ref().into());
+        }
+        self
+    }
+
+    /// At user id, DingTalk userid instead of mobile
+    pub fn at_user_id(mut self, user_id: &str) -> Self {
+        self.at_user_ids.push(user_id.into());
+        self
+    }
+
+    /// Add extra top level field, see `extra_fields`
+    pub fn extra_field(mut self, name: &str, value: Value) -> Self {
+        let extra_fields = self.extra_fields.get_or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Some(extra_fields) = extra_fields.as_object_mut() {
+            extra_fields.insert(name.into(), value);
+        }
+        self
+    }
+
+    /// Message type
+    pub fn kind(&self) -> DingTalkMessageType {
+        self.message_type
+    }
+
+    /// Required content of message type is missing
+    /// 
+    /// * Text - text content
+    /// * Markdown - title or content
+    /// * Link - title, text or message url
+    /// * ActionCard - title or text
+    /// * FeedCard - links
+    pub fn is_empty(&self) -> bool {
+        match self.message_type {
+            DingTalkMessageType::Text => self.text_content.is_empty(),
+            DingTalkMessageType::Markdown => self.markdown_title.is_empty() || self.markdown_content.is_empty(),
+            DingTalkMessageType::Link => self.link_title.is_empty() || self.link_text.is_empty() || self.link_message_url.is_empty(),
+            DingTalkMessageType::ActionCard => self.action_card_title.is_empty() || self.action_card_text.is_empty(),
+            DingTalkMessageType::FeedCard => self.feed_card_links.is_empty(),
+        }
+    }
+
+    /// Message contains any of `keywords`, for robot with custom keyword security
+    /// 
+    /// Checks text, markdown title and content, link title and text, action card title and text, feed card link titles
+    pub fn contains_keyword(&self, keywords: &[String]) -> bool {
+        let texts: Vec<&str> = match self.message_type {
+            DingTalkMessageType::Text => vec![&self.text_content],
+            DingTalkMessageType::Markdown => vec![&self.markdown_title, &self.markdown_content],
+            DingTalkMessageType::Link => vec![&self.link_title, &self.link_text],
+            DingTalkMessageType::ActionCard => vec![&self.action_card_title, &self.action_card_text],
+            DingTalkMessageType::FeedCard => self.feed_card_links.iter().map(|l| l.title.as_str()).collect(),
+        };
+        keywords.iter().any(|k| texts.iter().any(|t| t.contains(k.as_str())))
+    }
+
+    /// Append `keyword` when message does not contain it
+    /// 
+    /// Appended to text, markdown content, link text, action card text or the first feed card link title
+    pub fn ensure_keyword(mut self, keyword: &str) -> Self {
+        if self.contains_keyword(&[keyword.to_owned()]) {
+            return self;
+        }
+        match self.message_type {
+            DingTalkMessageType::Text => append_keyword(&mut self.text_content, "\n", keyword),
+            DingTalkMessageType::Markdown => append_keyword(&mut self.markdown_content, "\n\n", keyword),
+            DingTalkMessageType::Link => append_keyword(&mut self.link_text, " ", keyword),
+            DingTalkMessageType::ActionCard => append_keyword(&mut self.action_card_text, "\n\n", keyword),
+            DingTalkMessageType::FeedCard => if let Some(link) = self.feed_card_links.first_mut() {
+                append_keyword(&mut link.title, " ", keyword);
+            },
+        }
+        self
+    }
+
+    /// Append `@<mobile>` of `at_mobiles` and `@<user id>` of `at_user_ids` to text or markdown content,
+    /// DingTalk notifies only users which are both in `at` and content, mentions already in content are not appended,
+    /// nothing is appended when `at_all`
+    pub fn append_at_mentions(mut self) -> Self {
+        if self.at_all {
+            return self;
+        }
+        let (content, separator) = match self.message_type {
+            DingTalkMessageType::Text => (&mut self.text_content, "\n"),
+            DingTalkMessageType::Markdown => (&mut self.markdown_content, "\n\n"),
+            _ => return self,
+        };
+        let mentions: Vec<String> = self.at_mobiles.iter().chain(self.at_user_ids.iter())
+            .map(|m| format!("@{}", m))
+            .filter(|m| !content.contains(m.as_str()))
+            .collect();
+        if !mentions.is_empty() {
+            append_keyword(content, separator, &mentions.join(" "));
+        }
+        self
+    }
+
+    /// Validate message, invalid message is `DingTalkError::InvalidMessage`
+    /// 
+    /// * Text content, Markdown title and content are not empty
+    /// * ActionCard has single btn or btns
+    /// * FeedCard has links
+    /// * Markdown image URLs are https, DingTalk does not show http images
+    /// * Text/Markdown content max 20000 bytes
+    /// * ActionCard text max 5000 bytes
+    /// * Extra fields is a JSON object
+    pub fn validate(&self) -> XResult<()> {
+        if let Some(extra_fields) = &self.extra_fields {
+            if !extra_fields.is_object() {
+                return Err(DingTalkError::InvalidMessage(format!("Extra fields is not JSON object: {}", extra_fields)));
+            }
+        }
+        match self.message_type {
+            DingTalkMessageType::Text => {
+                check_not_empty("Text content", &self.text_content)?;
+                check_max_bytes("Text content", &self.text_content, TEXT_CONTENT_MAX_BYTES)
+            },
+            DingTalkMessageType::Markdown => {
+                check_not_empty("Markdown title", &self.markdown_title)?;
+                check_max_bytes("Markdown title", &self.markdown_title, MARKDOWN_TITLE_MAX_BYTES)?;
+                check_not_empty("Markdown content", &self.markdown_content)?;
+                check_markdown_images(&self.markdown_content)?;
+                check_max_bytes("Markdown content", &self.markdown_content, TEXT_CONTENT_MAX_BYTES)
+            },
+            DingTalkMessageType::ActionCard => {
+                match (&self.action_card_single_btn, self.action_card_btns.len()) {
+                    (None, 0) => return Err(DingTalkError::InvalidMessage("ActionCard has neither single btn nor btns".into())),
+                    (Some(_), n) if n > 0 => return Err(DingTalkError::InvalidMessage("ActionCard has both single btn and btns".into())),
+                    (_, n) if n > ACTION_CARD_MAX_BTNS => return Err(DingTalkError::InvalidMessage(
+                        format!("ActionCard has {} btns, max is {}", n, ACTION_CARD_MAX_BTNS))),
+                    _ => (),
+                }
+                check_max_bytes("ActionCard text", &self.action_card_text, ACTION_CARD_TEXT_MAX_BYTES)
+            },
+            DingTalkMessageType::FeedCard => {
+                if self.feed_card_links.is_empty() {
+                    return Err(DingTalkError::InvalidMessage("FeedCard has no links".into()));
+                }
+                Ok(())
+            },
+            DingTalkMessageType::Link => Ok(()),
+        }
+    }
+
+    /// Create DingTalk JSON message
+    pub(crate) fn to_json_value(&self) -> XResult<Value> {
+        let mut message_json = match self.message_type {
+            DingTalkMessageType::Text => serde_json::to_value(InnerTextMessage {
+                msgtype: DingTalkMessageType::Text,
+                text: InnerTextMessageText {
+                    content: self.text_content.clone(),
+                }
+            }),
+            DingTalkMessageType::Link => serde_json::to_value(InnerLinkMessage {
+                msgtype: DingTalkMessageType::Link,
+                link: InnerLinkMessageLink {
+                    title: self.link_title.clone(),
+                    text: self.link_text.clone(),
+                    pic_url: self.link_pic_url.clone(),
+                    message_url: self.link_message_url.clone(),
+                }
+            }),
+            DingTalkMessageType::Markdown => serde_json::to_value(InnerMarkdownMessage {
+                msgtype: DingTalkMessageType::Markdown,
+                markdown: InnerMarkdownMessageMarkdown {
+                    title: self.markdown_title.clone(),
+                    text: self.markdown_content.clone(),
+                }
+            }),
+            DingTalkMessageType::ActionCard => serde_json::to_value(InnerActionCardMessage {
+                msgtype: DingTalkMessageType::ActionCard,
+                action_card: InnerActionCardMessageActionCard {
+                    title: self.action_card_title.clone(),
+                    text: self.action_card_text.clone(),
+                    hide_avatar: self.action_card_hide_avatar,
+                    btn_orientation: self.action_card_btn_orientation,
+                }
+            }),
+            DingTalkMessageType::FeedCard => serde_json::to_value(InnerFeedCardMessage {
+                msgtype: DingTalkMessageType::FeedCard,
+                feed_card: InnerFeedCardMessageFeedCard {
+                    links: {
+                        let mut links: Vec<InnerFeedCardMessageFeedCardLink> = vec![];
+                        for feed_card_link in &self.feed_card_links {
+                            links.push(InnerFeedCardMessageFeedCardLink {
+                                title: feed_card_link.title.clone(),
+                                message_url: feed_card_link.message_url.clone(),
+                                pic_url: feed_card_link.pic_url.clone(),
+                            });
+                        }
+                        links
+                    }
+                }
+            })
+        }?;
+        if DingTalkMessageType::ActionCard == self.message_type {
+            if self.action_card_single_btn.is_some() {
+                if let Some(single_btn) = self.action_card_single_btn.as_ref() {
+                    message_json["actionCard"]["singleTitle"] = single_btn.title.as_str().into();
+                    message_json["actionCard"]["singleURL"] = single_btn.action_url.as_str().into();
+                };
+            } else if !self.action_card_btns.is_empty() {
+                let mut btns: Vec<InnerActionCardMessageBtn> = vec![];
+                for action_card_btn in &self.action_card_btns {
+                    btns.push(InnerActionCardMessageBtn {
+                        title: action_card_btn.title.clone(),
+                        action_url: action_card_btn.action_url.clone(),
+                    });
+                }
+                message_json["actionCard"]["btns"] = serde_json::to_value(btns)?;
+            }
+        }
+        if let (Some(at_json), Some(m)) = (at_json_value(self.at_all, &self.at_mobiles, &self.at_user_ids), message_json.as_object_mut()) {
+            m.insert("at".into(), at_json);
+        }
+        if let (Some(Value::Object(extra_fields)), Some(m)) = (&self.extra_fields, message_json.as_object_mut()) {
+            for (name, value) in extra_fields {
+                m.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(message_json)
+    }
+}
+
+/// DingTalk JSON at block, `None` when nobody is @, mentions are ignored when `at_all`
+fn at_json_value(at_all: bool, at_mobiles: &[String], at_user_ids: &[String]) -> Option<Value> {
+    if !at_all && at_mobiles.is_empty() && at_user_ids.is_empty() {
+        return None;
+    }
+    let (at_mobiles, at_user_ids): (&[String], &[String]) = if at_all {
+        (&[], &[])
+    } else {
+        (at_mobiles, at_user_ids)
+    };
+    let mut at_map = serde_json::Map::new();
+    if !at_mobiles.is_empty() {
+        at_map.insert("atMobiles".into(), at_mobiles.iter().map(|m| Value::String(m.clone())).collect());
+    }
+    if !at_user_ids.is_empty() {
+        at_map.insert("atUserIds".into(), at_user_ids.iter().map(|u| Value::String(u.clone())).collect());
+    }
+    at_map.insert("isAtAll".into(), Value::Bool(at_all));
+    Some(Value::Object(at_map))
+}
+
+/// Split text into numbered chunks under text content limit, e.g. `(1/3) ...`, see `DingTalk::send_text_chunked`
+/// 
+/// Text within the limit is one chunk without prefix, empty text has no chunks,
+/// room for prefix is reserved only when text is split
+pub(crate) fn numbered_text_chunks(text: &str) -> Vec<String> {
+    if text.len() <= TEXT_CONTENT_MAX_BYTES {
+        return split_text_chunks(text, TEXT_CONTENT_MAX_BYTES);
+    }
+    let chunks = split_text_chunks(text, TEXT_CONTENT_MAX_BYTES - TEXT_CHUNK_PREFIX_MAX_BYTES);
+    if chunks.len() <= 1 {
+        return chunks;
+    }
+    let total = chunks.len();
+    chunks.into_iter().enumerate().map(|(i, chunk)| format!("({}/{}) {}", i + 1, total, chunk)).collect()
+}
+
+/// Split text into chunks of at most `max_bytes` on line boundaries, a longer line is split on char boundaries
+fn split_text_chunks(text: &str, max_bytes: usize) -> Vec<String> {
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    for mut line in text.split_inclusive('\n') {
+        while !line.is_empty() {
+            if chunk.len() + line.len() <= max_bytes {
+                chunk.push_str(line);
+                break;
+            }
+            if !chunk.is_empty() {
+                chunks.push(std::mem::take(&mut chunk));
+                continue;
+            }
+            let mut end = max_bytes;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            chunks.push(line[..end].to_owned());
+            line = &line[end..];
+        }
+    }
+    chunks.push(chunk);
+    chunks.iter()
+        .map(|chunk| chunk.trim_end_matches('\n'))
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| chunk.to_owned())
+        .collect()
+}
+
+/// Append `keyword` to `text`, after `separator` unless `text` is empty
+fn append_keyword(text: &mut String, separator: &str, keyword: &str) {
+    if !text.is_empty() {
+        text.push_str(separator);
+    }
+    text.push_str(keyword);
+}
+
+/// Check URLs of markdown images `![alt](url)` are https
+fn check_markdown_images(markdown_content: &str) -> XResult<()> {
+    let mut rest = markdown_content;
+    while let Some(image_start) = rest.find("![") {
+        rest = &rest[image_start + 2..];
+        let url_start = match rest.find("](") { Some(i) => i + 2, None => break, };
+        rest = &rest[url_start..];
+        let url = rest.split(|c: char| c == ')' || c.is_whitespace()).next().unwrap_or_default();
+        if !url.starts_with("https://") {
+            return Err(DingTalkError::InvalidMessage(format!("Markdown image URL is not https: {}", url)));
+        }
+    }
+    Ok(())
+}
+
+fn check_not_empty(field: &str, value: &str) -> XResult<()> {
+    if value.is_empty() {
+        return Err(DingTalkError::InvalidMessage(format!("{} is empty", field)));
+    }
+    Ok(())
+}
+
+fn check_max_bytes(field: &str, value: &str, max_bytes: usize) -> XResult<()> {
+    if value.len() > max_bytes {
+        return Err(DingTalkError::InvalidMessage(format!("{} too large: {} bytes, max {} bytes", field, value.len(), max_bytes)));
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerTextMessageText {
+    pub content: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerTextMessage {
+    pub msgtype: DingTalkMessageType,
+    pub text: InnerTextMessageText,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerLinkMessageLink {
+    pub title: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub pic_url: String,
+    pub message_url: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerLinkMessage {
+    pub msgtype: DingTalkMessageType,
+    pub link: InnerLinkMessageLink,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerMarkdownMessageMarkdown {
+    pub title: String,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerMarkdownMessage {
+    pub msgtype: DingTalkMessageType,
+    pub markdown: InnerMarkdownMessageMarkdown,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerActionCardMessageActionCard {
+    pub title: String,
+    pub text: String,
+    pub hide_avatar: DingTalkMessageActionCardHideAvatar,
+    pub btn_orientation: DingTalkMessageActionCardBtnOrientation,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerActionCardMessageBtn {
+    pub title: String,
+    #[serde(rename = "actionURL")]
+    pub action_url: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerActionCardMessage {
+    pub msgtype: DingTalkMessageType,
+    pub action_card: InnerActionCardMessageActionCard,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerFeedCardMessageFeedCardLink {
+    pub title: String,
+    #[serde(rename = "messageURL")]
+    pub message_url: String,
+    #[serde(rename = "picURL")]
+    pub pic_url: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InnerFeedCardMessageFeedCard {
+    pub links: Vec<InnerFeedCardMessageFeedCardLink>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerFeedCardMessage {
+    pub msgtype: DingTalkMessageType,
+    pub feed_card: InnerFeedCardMessageFeedCard,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InnerResponse {
+    pub errcode: Value,
+    pub errmsg: String,
+}
@@ -0,0 +1,40 @@
+use std::{ future::Future, pin::Pin };
+use crate::{ DingTalk, XResult };
+
+/// Future returned by `Notifier`
+#[cfg(not(target_arch = "wasm32"))]
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = XResult<()>> + Send + 'a>>;
+/// Future returned by `Notifier`, browser futures are not `Send`
+#[cfg(target_arch = "wasm32")]
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = XResult<()>> + 'a>>;
+
+/// Alert channel, e.g. DingTalk, Slack or email, store `Box<dyn Notifier>` to route alerts uniformly
+/// 
+/// ```ignore
+/// let notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(dt), Box::new(slack)];
+/// for notifier in &notifiers {
+///     notifier.notify("disk full").await?;
+/// }
+/// ```
+pub trait Notifier: Send + Sync {
+    /// Notify text
+    fn notify<'a>(&'a self, text: &'a str) -> NotifyFuture<'a>;
+
+    /// Notify title and markdown, default notifies `title` and `markdown` as text
+    fn notify_markdown<'a>(&'a self, title: &'a str, markdown: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            self.notify(&format!("{}\n{}", title, markdown)).await
+        })
+    }
+}
+
+/// `notify` sends text message, `notify_markdown` sends markdown message
+impl Notifier for DingTalk {
+    fn notify<'a>(&'a self, text: &'a str) -> NotifyFuture<'a> {
+        Box::pin(self.send_text(text))
+    }
+
+    fn notify_markdown<'a>(&'a self, title: &'a str, markdown: &'a str) -> NotifyFuture<'a> {
+        Box::pin(self.send_markdown(title, markdown))
+    }
+}
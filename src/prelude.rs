@@ -0,0 +1,20 @@
+//! Common imports, `use dingtalk::prelude::*;` to build and send messages
+//! 
+//! ```ignore
+//! use dingtalk::prelude::*;
+//! 
+//! let dt = DingTalk::new("<token>", "");
+//! dt.send_message(DingTalkMessage::new_text("Hello world!").at_all()).await?;
+//! ```
+pub use crate::{
+    DingTalk,
+    DingTalkBuilder,
+    DingTalkError,
+    DingTalkType,
+    DingTalkMessage,
+    DingTalkMessageType,
+    DingTalkMessageActionCardBtn,
+    DingTalkMessageFeedCardLink,
+    MarkdownBuilder,
+    XResult,
+};
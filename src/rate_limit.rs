@@ -0,0 +1,77 @@
+use std::{ sync::Mutex, time::{ Duration, Instant } };
+use crate::{ DingTalkError, XResult };
+
+/// Client side rate limiter, token bucket
+/// 
+/// DingTalk robot is throttled to 20 messages per minute, exceeding it gets the robot banned for a while.
+/// `DingTalk` shares the limiter with `Arc`, so cloned `DingTalk` uses the same budget.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: u32,
+    period: Duration,
+    fail_fast: bool,
+    // (last refill, available tokens)
+    state: Mutex<(Instant, f64)>,
+}
+
+impl RateLimiter {
+
+    /// Create `RateLimiter`, allows `capacity` messages per `period`,
+    /// `capacity` 0 or `period` zero is `DingTalkError::InvalidConfig`, always `DingTalkError::InvalidConfig` in wasm
+    pub fn new(capacity: u32, period: Duration) -> XResult<Self> {
+        check_available()?;
+        if capacity == 0 {
+            return Err(DingTalkError::InvalidConfig("rate limit must be at least 1 message per period".into()));
+        }
+        if period.is_zero() {
+            return Err(DingTalkError::InvalidConfig("rate limit period must not be zero".into()));
+        }
+        Ok(RateLimiter {
+            capacity,
+            period,
+            fail_fast: false,
+            state: Mutex::new((Instant::now(), capacity as f64)),
+        })
+    }
+
+    /// Create `RateLimiter`, allows `per_minute` messages per minute
+    pub fn per_minute(per_minute: u32) -> XResult<Self> {
+        Self::new(per_minute, Duration::from_secs(60))
+    }
+
+    /// Returns `DingTalkError::RateLimited` instead of waiting when budget is exhausted
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Is fail fast
+    pub fn is_fail_fast(&self) -> bool {
+        self.fail_fast
+    }
+
+    /// Take one token, returns `Err(wait)` when budget is exhausted, `wait` is time until next token
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(state.0).as_secs_f64() / self.period.as_secs_f64() * self.capacity as f64;
+        *state = (now, (state.1 + refill).min(self.capacity as f64));
+        if state.1 >= 1_f64 {
+            state.1 -= 1_f64;
+            return Ok(());
+        }
+        let wait = (1_f64 - state.1) / self.capacity as f64 * self.period.as_secs_f64();
+        Err(Duration::from_secs_f64(wait))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn check_available() -> XResult<()> {
+    Ok(())
+}
+
+/// `Instant` panics in browser
+#[cfg(target_arch = "wasm32")]
+fn check_available() -> XResult<()> {
+    Err(DingTalkError::InvalidConfig("rate limit is not available in wasm".into()))
+}
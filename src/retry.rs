@@ -0,0 +1,110 @@
+use std::{ sync::{ Arc, Mutex }, time::{ Duration, Instant, SystemTime } };
+use crate::{ Clock, SystemClock };
+
+/// Retry budget, caps total retry attempts per time window
+/// 
+/// Share one budget between robots with `Arc<RetryBudget>`, so a storm of failures
+/// does not multiply retries across all robots.
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_retries: u32,
+    window: Duration,
+    // (window start, used retries)
+    state: Mutex<(Instant, u32)>,
+}
+
+impl RetryBudget {
+
+    /// Create `RetryBudget`, allows `max_retries` retries per `window`, not available in wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(max_retries: u32, window: Duration) -> Self {
+        RetryBudget {
+            max_retries,
+            window,
+            state: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Take one retry from budget, returns `false` when budget is exhausted
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.reset_expired_window(&mut state);
+        if state.1 >= self.max_retries {
+            return false;
+        }
+        state.1 += 1;
+        true
+    }
+
+    /// Remaining retries in current window
+    pub fn remaining(&self) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.reset_expired_window(&mut state);
+        self.max_retries - state.1
+    }
+
+    fn reset_expired_window(&self, state: &mut (Instant, u32)) {
+        if state.0.elapsed() >= self.window {
+            *state = (Instant::now(), 0);
+        }
+    }
+}
+
+/// Retry policy, exponential backoff with jitter
+/// 
+/// Retries connection errors, timeouts, HTTP 429 and 5xx, see `DingTalkError::is_retryable`.
+/// Delay of retry `n`(starts from 0) is between `base_delay * 2^n / 2` and `base_delay * 2^n`, capped by `max_delay`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    budget: Option<Arc<RetryBudget>>,
+}
+
+impl RetryPolicy {
+
+    /// Create `RetryPolicy`, retries at most `max_retries` times, `max_delay` defaults to 30 seconds
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            base_delay,
+            max_delay: Duration::from_secs(30),
+            budget: None,
+        }
+    }
+
+    /// Set max delay between retries
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set retry budget, retry only when budget is not exhausted
+    pub fn budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Max retries
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Delay before retry `retry`(starts from 0)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.base_delay.checked_mul(2_u32.saturating_pow(retry)).unwrap_or(self.max_delay).min(self.max_delay);
+        let half = delay / 2;
+        half + jitter(delay - half)
+    }
+
+    pub(crate) fn try_acquire_budget(&self) -> bool {
+        self.budget.as_ref().map(|b| b.try_acquire()).unwrap_or(true)
+    }
+}
+
+/// Random duration in `[0, max]`, randomness from clock is enough for spreading retries
+fn jitter(max: Duration) -> Duration {
+    let nanos = SystemClock.now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+    max.mul_f64(f64::from(nanos % 1_000_001) / 1_000_000_f64)
+}
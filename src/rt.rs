@@ -0,0 +1,106 @@
+use std::{ future::Future, pin::Pin, time::{ Duration, SystemTime } };
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Async sleep, used when `DingTalk` needs to wait (e.g. rate limit, retry)
+/// 
+/// Default implementation is `DefaultSleep`, implement this trait to use other runtime's timer
+pub trait Sleep: Send + Sync {
+    /// Sleep for `duration`
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Default sleep
+/// 
+/// * With feature `tokio`(default) uses `tokio::time::delay_for`
+/// * Without feature `tokio` uses a timer thread, works with any runtime(e.g. `async-std`, `smol`)
+/// * Not available in WASM, there is no thread or `tokio` timer in browser, set `DingTalkBuilder::sleep` there
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultSleep;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Sleep for DefaultSleep {
+    #[cfg(feature = "tokio")]
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            tokio::time::delay_for(duration).await;
+        })
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = tx.send(());
+        });
+        Box::pin(async move {
+            let _ = rx.await;
+        })
+    }
+}
+
+/// Clock, used when signing webhook URL
+/// 
+/// Default implementation is `SystemClock`, use `FixedClock` to get reproducible `timestamp` and `sign`
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> SystemTime;
+}
+
+/// System clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// `SystemTime::now` panics in browser, use `Date.now()`
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(js_sys::Date::now() as u64)
+    }
+}
+
+/// Fixed clock, always returns the same time, for tests
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// Measures elapsed time, monotonic `Instant`, wall clock in browser where `Instant` is not available
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct Stopwatch(Instant);
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct Stopwatch(SystemTime);
+
+impl Stopwatch {
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start() -> Self {
+        Stopwatch(Instant::now())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn start() -> Self {
+        Stopwatch(SystemClock.now())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        SystemClock.now().duration_since(self.0).unwrap_or_default()
+    }
+}
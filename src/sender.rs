@@ -0,0 +1,41 @@
+use futures::{ Stream, StreamExt };
+use crate::{ DingTalk, DingTalkError, DingTalkMessage };
+
+impl DingTalk {
+
+    /// Send messages from `messages` one by one until the stream ends, e.g. alerts collected from a channel
+    /// 
+    /// Each message is sent by `send_message`, with rate limiter, retries and dead letter,
+    /// a failed message is logged(feature `tracing`) and does not stop sending the others.
+    /// Stops when cancellation token(feature `cancellation`) is cancelled, even while waiting for messages.
+    pub async fn send_stream<S>(&self, messages: S) where S: Stream<Item = DingTalkMessage> {
+        futures::pin_mut!(messages);
+        while let Ok(Some(dingtalk_message)) = self.cancellable(async { Ok(messages.next().await) }).await {
+            if let Err(DingTalkError::Cancelled) = self.send_message(dingtalk_message).await {
+                break;
+            }
+        }
+    }
+
+    /// Spawn a `tokio` task sending messages from `rx`, see `send_stream`
+    /// 
+    /// The task ends when all senders are dropped and received messages are sent,
+    /// or when cancellation token(feature `cancellation`) is cancelled.
+    /// 
+    /// ```ignore
+    /// let (tx, rx) = tokio::sync::mpsc::channel(100);
+    /// let handle = dt.spawn_sender(rx);
+    /// tx.send(DingTalkMessage::new_text("disk full")).await?;
+    /// drop(tx);
+    /// handle.await?;
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn spawn_sender(self, rx: tokio::sync::mpsc::Receiver<DingTalkMessage>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let messages = futures::stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|dingtalk_message| (dingtalk_message, rx))
+            });
+            self.send_stream(messages).await
+        })
+    }
+}
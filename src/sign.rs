@@ -0,0 +1,225 @@
+use std::{ sync::Arc, time::SystemTime };
+use sha2::Sha256;
+use hmac::{ Hmac, Mac };
+use serde::{ Serialize, Deserialize };
+use crate::{ Clock, DingTalk, DingTalkError, DingTalkType, SystemClock, XResult };
+use crate::config::base_host_webhook_url;
+
+/// Webhook URL query param names, for proxy gateways with non-standard names
+/// 
+/// `None` uses the default name
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DingTalkWebhookParams {
+    /// Access token param name, default `access_token`, WeChat Work default `key`
+    pub token: Option<String>,
+    /// Timestamp param name, default `timestamp`
+    pub timestamp: Option<String>,
+    /// Sign param name, default `sign`
+    pub sign: Option<String>,
+}
+
+impl DingTalk {
+
+    /// Set webhook URL from base and path, e.g. `https://gateway.example.com` and `/dingtalk/send`
+    pub fn set_webhook_base_path(&mut self, base: &str, path: &str) {
+        self.default_webhook_url = format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/'));
+    }
+
+    /// Set webhook URL to the robot path of type on `base_host`, for region-specific or dedicated deployments
+    /// 
+    /// e.g. `https://oapi.example.com` or `oapi.example.com` is `https://oapi.example.com/robot/send`,
+    /// `base_host` must be https without path, otherwise `DingTalkError::InvalidConfig`
+    pub fn set_base_host(&mut self, base_host: &str) -> XResult<()> {
+        self.default_webhook_url = base_host_webhook_url(self.dingtalk_type, base_host)?;
+        Ok(())
+    }
+
+    /// Set webhook URL on `base_host`, see `set_base_host`
+    pub fn with_base_host(mut self, base_host: &str) -> XResult<Self> {
+        self.set_base_host(base_host)?;
+        Ok(self)
+    }
+
+    /// Allow http webhook URL, see `DingTalkBuilder::allow_insecure`
+    pub fn allow_insecure(mut self) -> Self {
+        self.allow_insecure = true;
+        self
+    }
+
+    /// Webhook URL must be https unless `allow_insecure`, http sends token and sign in cleartext
+    pub(crate) fn check_webhook_url_scheme(&self) -> XResult<()> {
+        let webhook_url = self.webhook_url();
+        let is_https = match reqwest::Url::parse(webhook_url) {
+            Ok(url) => url.scheme() == "https",
+            // let HTTP client report invalid URL
+            Err(_) => true,
+        };
+        if is_https || self.allow_insecure {
+            return Ok(());
+        }
+        Err(DingTalkError::InvalidConfig(format!(
+            "webhook URL must be https, allow_insecure() allows http for local testing: {}", redact_webhook_url(webhook_url))))
+    }
+
+    /// Access token is required unless direct URL, otherwise send fails with a confusing DingTalk error
+    pub(crate) fn check_access_token(&self) -> XResult<()> {
+        if self.access_token.is_empty() && self.direct_url.is_empty() {
+            return Err(DingTalkError::InvalidConfig("missing access_token".into()));
+        }
+        Ok(())
+    }
+
+    /// Set webhook URL query param names
+    pub fn set_webhook_params(&mut self, webhook_params: DingTalkWebhookParams) {
+        self.webhook_params = webhook_params;
+    }
+
+    /// Set clock, used when signing
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+    }
+
+    /// Sign of `timestamp_millis`, for building signed webhook URL yourself
+    /// 
+    /// Returns `Base64(HmacSHA256("{timestamp}\n{secret}"))` with key `secret`, that is `sec_token`,
+    /// e.g. signed string of timestamp `1600000000000` and secret `SEC0123` is `"1600000000000\nSEC0123"`.
+    /// The sign should be percent-encoded in URL query.
+    pub fn sign(&self, timestamp_millis: u128) -> String {
+        let timestamp_and_secret = format!("{}\n{}", timestamp_millis, self.sec_token);
+        base64::encode(&Signer::default().sign(self.sec_token.as_bytes(), timestamp_and_secret.as_bytes()))
+    }
+
+    /// Generate signed dingtalk webhook URL
+    pub fn generate_signed_url(&self) -> XResult<String> {
+        self.generate_signed_url_with_token(&self.access_token)
+    }
+
+    /// Generate signed dingtalk webhook URL with access token `access_token`
+    pub fn generate_signed_url_with_token(&self, access_token: &str) -> XResult<String> {
+        self.check_webhook_url_scheme()?;
+        if !self.direct_url.is_empty() {
+            return Ok(self.direct_url.clone());
+        }
+        let mut signed_url = String::with_capacity(1024);
+        signed_url.push_str(&self.default_webhook_url);
+
+        if self.default_webhook_url.ends_with('?') {
+            // Just Ok
+        } else if self.default_webhook_url.contains('?') {
+            if !self.default_webhook_url.ends_with('&') {
+                signed_url.push('&');
+            }
+        } else {
+            signed_url.push('?');
+        }
+
+        let token_param = self.webhook_params.token.as_deref().unwrap_or(match self.dingtalk_type {
+            DingTalkType::DingTalk => "access_token",
+            DingTalkType::WeChatWork => "key",
+        });
+        signed_url.push_str(token_param);
+        signed_url.push('=');
+        if self.token_pre_encoded {
+            signed_url.push_str(access_token);
+        } else {
+            signed_url.push_str(&urlencoding::encode(access_token));
+        }
+
+        if !self.sec_token.is_empty() {
+            let now = match &self.clock { Some(clock) => clock.now(), None => SystemClock.now(), };
+            let since_epoch = now.duration_since(SystemTime::UNIX_EPOCH)
+                .map_err(|e| DingTalkError::Clock(format!("system time is before UNIX epoch: {:?}", e.duration())))?;
+            let timestamp = &format!("{}", since_epoch.as_millis());
+            let hmac_sha256 = self.sign(since_epoch.as_millis());
+
+            signed_url.push('&');
+            signed_url.push_str(self.webhook_params.timestamp.as_deref().unwrap_or("timestamp"));
+            signed_url.push('=');
+            signed_url.push_str(timestamp);
+            signed_url.push('&');
+            signed_url.push_str(self.webhook_params.sign.as_deref().unwrap_or("sign"));
+            signed_url.push('=');
+            signed_url.push_str(&urlencoding::encode(&hmac_sha256));
+        }
+
+        Ok(signed_url)
+    }
+}
+
+/// Verify `timestamp` and `sign` headers of outgoing robot request
+/// 
+/// `sign` is `Base64(HmacSHA256(timestamp + "\n" + app_secret))` with key `app_secret`, compared in constant time.
+/// Caller should also reject old `timestamp`, DingTalk requires it within 1 hour.
+pub fn verify_signature(app_secret: &str, timestamp: &str, sign: &str) -> bool {
+    let sign = match base64::decode(sign) { Ok(s) => s, Err(_) => return false, };
+    Signer::default().verify(app_secret.as_bytes(), format!("{}\n{}", timestamp, app_secret).as_bytes(), &sign)
+}
+
+/// URL without query and user info, query of webhook URL has token and sign
+pub(crate) fn redact_url(url: &reqwest::Url) -> String {
+    let mut redacted_url = url.clone();
+    redacted_url.set_query(None);
+    redacted_url.set_fragment(None);
+    let _ = redacted_url.set_username("");
+    let _ = redacted_url.set_password(None);
+    redacted_url.to_string()
+}
+
+/// Webhook URL without query, for `Debug`, not a URL is kept until `?`
+pub(crate) fn redact_webhook_url(webhook_url: &str) -> String {
+    match reqwest::Url::parse(webhook_url) {
+        Ok(url) => redact_url(&url),
+        Err(_) => webhook_url.split('?').next().unwrap_or_default().into(),
+    }
+}
+
+/// Mask secret, keeps first 2 chars of secret longer than 8 chars, e.g. `ab***`
+pub(crate) fn mask_secret(secret: &str) -> String {
+    match secret.chars().count() {
+        0 => String::new(),
+        n if n > 8 => format!("{}***", secret.chars().take(2).collect::<String>()),
+        _ => "***".into(),
+    }
+}
+
+/// HMAC hash algorithm, DingTalk only signs with SHA256 now
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SignAlgorithm {
+    #[default]
+    HmacSha256,
+}
+
+/// HMAC signer, the only place depends on crypto crates
+#[derive(Clone, Copy, Debug, Default)]
+struct Signer {
+    algorithm: SignAlgorithm,
+}
+
+impl Signer {
+
+    /// HMAC digest of `message` with key `key`
+    fn sign(&self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self.algorithm {
+            SignAlgorithm::HmacSha256 => {
+                // HMAC pads or hashes key, any key size is OK
+                let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC key of any size");
+                mac.input(message);
+                mac.result().code().to_vec()
+            },
+        }
+    }
+
+    /// Verify HMAC digest `sign` of `message` with key `key`
+    fn verify(&self, key: &[u8], message: &[u8], sign: &[u8]) -> bool {
+        match self.algorithm {
+            SignAlgorithm::HmacSha256 => {
+                let mut mac = match Hmac::<Sha256>::new_varkey(key) { Ok(m) => m, Err(_) => return false, };
+                mac.input(message);
+                // `Mac::verify` compares in constant time(`subtle`), `==` returns at the first different byte,
+                // the response time would tell an attacker how many leading bytes of a forged sign are right
+                mac.verify(sign).is_ok()
+            },
+        }
+    }
+}
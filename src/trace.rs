@@ -0,0 +1,9 @@
+/// Emit `tracing` event when feature `tracing` is on, otherwise do nothing
+/// 
+/// NEVER log `sec_token`, access token or signed URL, use `redact_url`
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
@@ -0,0 +1,826 @@
+use std::{ collections::BTreeMap, fmt, future::Future, pin::Pin, sync::{ Arc, Mutex, OnceLock, atomic::{ AtomicU32, Ordering } }, time::Duration };
+use reqwest::header::{ HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE };
+use futures::StreamExt;
+use serde_json::Value;
+use crate::{ AtConfig, Clock, Deduplicator, DingTalk, DingTalkError, DingTalkMessage, DingTalkMessageActionCardBtn, DingTalkMessageFeedCardLink, DingTalkMessageType, RateLimiter, RetryPolicy, Sleep, SystemClock, XResult };
+use crate::message::{ InnerResponse, numbered_text_chunks };
+use crate::config::read_text_file;
+use crate::rt::Stopwatch;
+#[cfg(feature = "tracing")]
+use crate::sign::redact_url;
+
+const APPLICATION_JSON_UTF8: &str = "application/json; charset=utf-8";
+
+/// Response body of dry run
+const DRY_RUN_RESPONSE: &str = r#"{"errcode":0,"errmsg":"dry run"}"#;
+const DEDUPLICATED_RESPONSE: &str = r#"{"errcode":0,"errmsg":"deduplicated"}"#;
+/// Response body of `MockTransport` by default
+const MOCK_OK_RESPONSE: &str = r#"{"errcode":0,"errmsg":"ok"}"#;
+
+/// DingTalk errcode when security check failed (keyword, sign or IP)
+const ERRCODE_SECURITY_FAILED: i64 = 310000;
+/// DingTalk errcode when sending too frequently, robot is throttled for a while
+const ERRCODE_SEND_TOO_FAST: i64 = 130101;
+/// Cooldown of `ERRCODE_SEND_TOO_FAST`, DingTalk throttles robot for about a minute
+const SEND_TOO_FAST_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Send outcome
+#[derive(Clone, Debug)]
+pub struct SendOutcome {
+    /// Request is signed with `sec_token`, otherwise keyword or IP security is used
+    pub signed: bool,
+    /// Host of webhook URL, without token
+    pub host: String,
+    /// Message is not sent, the identical message was sent within dedup window, see `DingTalk::with_dedup`
+    pub deduplicated: bool,
+}
+
+/// Send metrics, returned by `DingTalk::send_with_metrics`
+#[derive(Clone, Debug)]
+pub struct SendMetrics {
+    /// Attempts, 1 plus retries, 0 when not sent(dry run or deduplicated)
+    pub attempts: u32,
+    /// Elapsed time, including waiting for rate limiter and between retries
+    pub elapsed: Duration,
+    /// HTTP status of the last response, `None` when not sent
+    pub status: Option<u16>,
+}
+
+/// Response of one send
+struct Sent {
+    /// HTTP status, `None` in dry run
+    status: Option<u16>,
+    body: String,
+}
+
+/// Send response, `errcode` is always 0 when returned by `DingTalk::send_with_response`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendResponse {
+    pub errcode: i64,
+    pub errmsg: String,
+}
+
+impl SendResponse {
+
+    /// Parse response body, `errcode` can be number or numeric string
+    pub(crate) fn parse(body: &str) -> XResult<Self> {
+        let response: InnerResponse = serde_json::from_str(body)
+            .map_err(|e| DingTalkError::UnexpectedResponse(format!("response is not JSON: {}", e)))?;
+        Ok(SendResponse {
+            errcode: parse_errcode(&response.errcode)?,
+            errmsg: response.errmsg,
+        })
+    }
+}
+
+/// Per send options
+#[derive(Clone, Default)]
+pub struct SendOptions {
+    /// Extra headers of this request only, e.g. `X-Idempotency-Key` for a dedup proxy
+    /// 
+    /// Per send headers take precedence over `DingTalk::headers` and default headers(e.g. `Content-Type`)
+    pub headers: BTreeMap<String, String>,
+    /// Access token of this request only, `DingTalk::access_token` when `None`
+    /// 
+    /// `sec_token` is not overridden, robots signed with different secrets need their own `DingTalk`
+    pub access_token: Option<String>,
+}
+
+/// `access_token` is redacted
+impl fmt::Debug for SendOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendOptions")
+            .field("headers", &self.headers)
+            .field("access_token", &self.access_token.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl SendOptions {
+
+    /// Add header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set access token
+    pub fn access_token(mut self, access_token: &str) -> Self {
+        self.access_token = Some(access_token.into());
+        self
+    }
+}
+
+/// HTTP transport, POSTs signed webhook URL and JSON message
+/// 
+/// `DingTalk` sends with its `reqwest::Client` when transport is not set,
+/// implement this trait to intercept sends, e.g. `MockTransport` in unit tests of code sending alerts.
+/// Signing, headers, rate limit, retries, dedup and `errcode` check are still done by `DingTalk`,
+/// timeout and proxy are up to the transport.
+pub trait Transport: Send + Sync {
+    /// POST `body` with `headers` to `url`, returns response body when HTTP status is 200,
+    /// otherwise `DingTalkError::Status`(or `DingTalkError::RateLimited` when 429)
+    /// 
+    /// `headers` are `Content-Type`, `DingTalk::headers` and per send `SendOptions::headers`
+    fn post(&self, url: &str, headers: &HeaderMap, body: &str) -> Pin<Box<dyn Future<Output = XResult<String>> + Send + '_>>;
+}
+
+/// Request sent to `MockTransport`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockTransportRequest {
+    /// Signed webhook URL, contains access token and sign
+    pub url: String,
+    /// Request headers, e.g. `Content-Type`
+    pub headers: HeaderMap,
+    /// JSON message
+    pub body: String,
+}
+
+/// Mock transport, records requests and responds `{"errcode":0,"errmsg":"ok"}`, nothing is sent
+/// 
+/// ```ignore
+/// let transport = Arc::new(MockTransport::new());
+/// let dt = DingTalk::builder().access_token("token").transport(transport.clone()).build()?;
+/// dt.send_text("disk full").await?;
+/// assert_eq!(1, transport.requests().len());
+/// ```
+#[derive(Debug)]
+pub struct MockTransport {
+    response: String,
+    requests: Mutex<Vec<MockTransportRequest>>,
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        MockTransport::with_response(MOCK_OK_RESPONSE)
+    }
+}
+
+impl MockTransport {
+
+    /// Mock transport responds `{"errcode":0,"errmsg":"ok"}`
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// Mock transport responds `response` to every request, e.g. error response to test error handling
+    pub fn with_response(response: &str) -> Self {
+        MockTransport {
+            response: response.into(),
+            requests: Mutex::new(vec![]),
+        }
+    }
+
+    /// Requests received, in order
+    pub fn requests(&self) -> Vec<MockTransportRequest> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// JSON messages received, in order
+    pub fn sent_messages(&self) -> Vec<String> {
+        self.requests().into_iter().map(|r| r.body).collect()
+    }
+}
+
+impl Transport for MockTransport {
+    fn post(&self, url: &str, headers: &HeaderMap, body: &str) -> Pin<Box<dyn Future<Output = XResult<String>> + Send + '_>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).push(MockTransportRequest {
+            url: url.into(),
+            headers: headers.clone(),
+            body: body.into(),
+        });
+        Box::pin(futures::future::ready(Ok(self.response.clone())))
+    }
+}
+
+impl DingTalk {
+
+    /// Send DingTalk message
+    /// 
+    /// 1. Validate DingTalk message
+    /// 2. Create DingTalk JSON message
+    /// 3. POST JSON message to DingTalk server
+    /// 4. When failed, send diagnostic message to dead letter robot(if set)
+    pub async fn send_message(&self, dingtalk_message: DingTalkMessage) -> XResult<()> {
+        self.send_message_with_options(dingtalk_message, &SendOptions::default()).await
+    }
+
+    /// Send DingTalk message with `SendOptions`
+    pub async fn send_message_with_options(&self, dingtalk_message: DingTalkMessage, options: &SendOptions) -> XResult<()> {
+        let message_type = dingtalk_message.kind();
+        trace_event!(debug, message_type = ?message_type, "send DingTalk message");
+        let result = self.send_message_without_dead_letter(dingtalk_message, options).await;
+        if let Err(e) = &result {
+            trace_event!(warn, message_type = ?message_type, error = %e, "send DingTalk message failed");
+            if let Some(dead_letter) = &self.dead_letter {
+                // dead letter robot is sent by `send`, which never sends dead letter, so no loop
+                if let Some(diagnostic_json) = self.dead_letter_json(message_type, e) {
+                    let _ = dead_letter.send(&diagnostic_json).await;
+                }
+            }
+        }
+        result
+    }
+
+    /// Send DingTalk message with access token `access_token`, e.g. dispatch to robots by routing rules
+    pub async fn send_message_with_token(&self, access_token: &str, dingtalk_message: DingTalkMessage) -> XResult<()> {
+        self.send_message_with_options(dingtalk_message, &SendOptions::default().access_token(access_token)).await
+    }
+
+    async fn send_message_without_dead_letter(&self, dingtalk_message: DingTalkMessage, options: &SendOptions) -> XResult<()> {
+        self.check_keywords(&dingtalk_message)?;
+        self.send_with_options(&self.build_message_json(&dingtalk_message)?, options).await
+    }
+
+    /// Check message contains any of `keywords`, error only when `keywords_required`
+    pub(crate) fn check_keywords(&self, dingtalk_message: &DingTalkMessage) -> XResult<()> {
+        if self.keywords.is_empty() || dingtalk_message.contains_keyword(&self.keywords) {
+            return Ok(());
+        }
+        if self.keywords_required {
+            return Err(DingTalkError::InvalidMessage(format!("message contains none of keywords: {:?}", self.keywords)));
+        }
+        trace_event!(warn, keywords = ?self.keywords, "DingTalk message contains no keyword, may be rejected");
+        Ok(())
+    }
+
+    /// Validate DingTalk message and create JSON message, exactly what `send_message` POSTs
+    /// 
+    /// Useful for logging or asserting the payload in tests without sending
+    pub fn build_message_json(&self, dingtalk_message: &DingTalkMessage) -> XResult<String> {
+        let appended_message;
+        let dingtalk_message = if self.auto_append_at {
+            appended_message = dingtalk_message.clone().append_at_mentions();
+            &appended_message
+        } else {
+            dingtalk_message
+        };
+        dingtalk_message.validate()?;
+        Ok(serde_json::to_string(&dingtalk_message.to_json_value()?)?)
+    }
+
+    /// Diagnostic JSON message sent to dead letter robot
+    pub(crate) fn dead_letter_json(&self, message_type: DingTalkMessageType, e: &DingTalkError) -> Option<String> {
+        let diagnostic = format!("DingTalk send failed, message type: {:?}, host: {}, error: {}",
+            message_type, self.webhook_host(), e);
+        DingTalkMessage::new_text(&diagnostic).to_json_value().ok().map(|v| v.to_string())
+    }
+
+    /// Set dead letter robot, receives a diagnostic message when `send_message` failed
+    pub fn set_dead_letter(&mut self, dead_letter: DingTalk) {
+        self.dead_letter = Some(Box::new(dead_letter));
+    }
+
+    /// Set HTTP client, e.g. client with proxy or custom TLS config
+    pub fn set_client(&mut self, client: reqwest::Client) {
+        self.client = Some(client);
+    }
+
+    /// Use HTTP client, e.g. application wide client with connection pool and tracing middleware,
+    /// when not set a shared default client is used
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.set_client(client);
+        self
+    }
+
+    fn client(&self) -> &reqwest::Client {
+        static SHARED_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+        match &self.client {
+            Some(client) => client,
+            None => SHARED_CLIENT.get_or_init(reqwest::Client::new),
+        }
+    }
+
+    /// Set custom keywords, `send_message` warns(with feature `tracing`) when message has no keyword
+    pub fn with_keywords(mut self, keywords: Vec<String>) -> Self {
+        self.keywords = keywords;
+        self.keywords_required = false;
+        self
+    }
+
+    /// Set custom keywords, `send_message` returns `DingTalkError::InvalidMessage` when message has no keyword
+    pub fn with_required_keywords(mut self, keywords: Vec<String>) -> Self {
+        self.keywords = keywords;
+        self.keywords_required = true;
+        self
+    }
+
+    /// Set request timeout, timeout is `DingTalkError::Timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set client side rate limit, `send` waits when more than `per_minute` messages are sent in a minute
+    /// 
+    /// `per_minute` 0 is `DingTalkError::InvalidConfig`
+    pub fn with_rate_limit(mut self, per_minute: u32) -> XResult<Self> {
+        self.set_rate_limiter(Arc::new(RateLimiter::per_minute(per_minute)?));
+        Ok(self)
+    }
+
+    /// Set dedup, a message identical to one sent within `window` is not sent, `send_with_outcome` tells it by `deduplicated`,
+    /// not available in wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_dedup(mut self, window: Duration) -> Self {
+        self.set_deduplicator(Arc::new(Deduplicator::new(window)));
+        self
+    }
+
+    /// Set deduplicator, share one deduplicator between robots
+    pub fn set_deduplicator(&mut self, deduplicator: Arc<Deduplicator>) {
+        self.deduplicator = Some(deduplicator);
+    }
+
+    /// Set client side rate limiter, share one limiter between robots with the same webhook
+    pub fn set_rate_limiter(&mut self, rate_limiter: Arc<RateLimiter>) {
+        self.rate_limiter = Some(rate_limiter);
+    }
+
+    /// Set retry policy, retries `max_retries` times with exponential backoff starting from `base_delay`,
+    /// in wasm retries only when `sleep` is set, `DefaultSleep` is not available in browser
+    pub fn with_retries(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.set_retry_policy(RetryPolicy::new(max_retries, base_delay));
+        self
+    }
+
+    /// Set retry policy
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = Some(retry_policy);
+    }
+
+    /// Set sleep, used when waiting for rate limiter and between retries
+    pub fn set_sleep(&mut self, sleep: Arc<dyn Sleep>) {
+        self.sleep = Some(sleep);
+    }
+
+    /// Set transport, e.g. `MockTransport` in unit tests
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = Some(transport);
+    }
+
+    /// Use transport, messages are POSTed by `transport` instead of HTTP client
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.set_transport(transport);
+        self
+    }
+
+    /// Send text message
+    pub async fn send_text(&self, text_message: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_text(text_message)).await
+    }
+
+    /// Send text message and @ all
+    pub async fn send_text_at_all(&self, text_message: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_text(text_message).at_all()).await
+    }
+
+    /// Send long text message, e.g. stack trace or logs, in chunks under the text content limit
+    /// 
+    /// Text is split on line boundaries, chunks are prefixed with `(1/3) ` style numbering
+    /// and sent in order one by one(with rate limiter), sending stops at the first failed chunk.
+    /// Text under the limit is sent as is, the same as `send_text`.
+    pub async fn send_text_chunked(&self, text_message: &str) -> XResult<()> {
+        let chunks = numbered_text_chunks(text_message);
+        if chunks.len() <= 1 {
+            return self.send_text(text_message).await;
+        }
+        for chunk in &chunks {
+            self.send_text(chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Send text message with access token `access_token`
+    pub async fn send_text_with_token(&self, access_token: &str, text_message: &str) -> XResult<()> {
+        self.send_message_with_token(access_token, DingTalkMessage::new_text(text_message)).await
+    }
+
+    /// Send markdown message
+    pub async fn send_markdown(&self, title: &str, text: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_markdown(title, text)).await
+    }
+
+    /// Send markdown message and @ all
+    pub async fn send_markdown_at_all(&self, title: &str, text: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_markdown(title, text).at_all()).await
+    }
+
+    /// Send markdown message, markdown content is read from file
+    pub async fn send_markdown_from_file(&self, title: &str, f: &str) -> XResult<()> {
+        let markdown_content = read_text_file(f)?;
+        self.send_markdown(title, &markdown_content).await
+    }
+
+    /// Send text message, text content is read from log file
+    pub async fn send_log_file(&self, f: &str) -> XResult<()> {
+        let log_content = read_text_file(f)?;
+        self.send_text(&log_content).await
+    }
+
+    /// Send link message
+    pub async fn send_link(&self, link_title: &str, link_text: &str, link_pic_url: &str, link_message_url: &str) -> XResult<()> {
+        self.send_message(DingTalkMessage::new_link(link_title, link_text, link_pic_url, link_message_url)).await
+    }
+
+    /// Send action card message
+    pub async fn send_action_card(&self, title: &str, text: &str, btns: Vec<DingTalkMessageActionCardBtn>) -> XResult<()> {
+        let mut dingtalk_message = DingTalkMessage::new_action_card(title, text);
+        dingtalk_message.action_card_btns = btns;
+        self.send_message(dingtalk_message).await
+    }
+
+    /// Send feed card message
+    pub async fn send_feed_card(&self, links: Vec<DingTalkMessageFeedCardLink>) -> XResult<()> {
+        let mut dingtalk_message = DingTalkMessage::new_feed_card();
+        dingtalk_message.feed_card_links = links;
+        self.send_message(dingtalk_message).await
+    }
+
+    /// Send DingTalk messages concurrently, at most `concurrency` sends are in flight, results are in order of messages
+    /// 
+    /// Each send goes through rate limiter and retries as `send_message`, a failed send does not stop the others
+    pub async fn send_many(&self, dingtalk_messages: &[DingTalkMessage], concurrency: usize) -> Vec<XResult<()>> {
+        let sends = dingtalk_messages.iter().map(|m| self.send_message(m.clone()));
+        futures::stream::iter(sends).buffered(concurrency.max(1)).collect().await
+    }
+
+    /// Direct send JSON message
+    pub async fn send(&self, json_message: &str) -> XResult<()> {
+        self.send_with_outcome(json_message).await?;
+        Ok(())
+    }
+
+    /// Direct send JSON message with at block `at`, e.g. cached serialized message
+    /// 
+    /// `at` replaces `at` of JSON message, JSON message is unchanged when nobody is @,
+    /// JSON message which is not object is `DingTalkError::InvalidMessage`
+    pub async fn send_with_at(&self, json_message: &str, at: AtConfig) -> XResult<()> {
+        let at_json = match at.to_json_value() {
+            Some(at_json) => at_json,
+            None => return self.send(json_message).await,
+        };
+        let mut message_json: Value = serde_json::from_str(json_message)?;
+        match message_json.as_object_mut() {
+            Some(m) => { m.insert("at".into(), at_json); },
+            None => return Err(DingTalkError::InvalidMessage("JSON message is not object".into())),
+        }
+        self.send(&message_json.to_string()).await
+    }
+
+    /// Direct send JSON message with `SendOptions`
+    pub async fn send_with_options(&self, json_message: &str, options: &SendOptions) -> XResult<()> {
+        self.send_inner(json_message, options).await?;
+        Ok(())
+    }
+
+    /// Direct send JSON message, returns `SendOutcome`
+    pub async fn send_with_outcome(&self, json_message: &str) -> XResult<SendOutcome> {
+        let body = self.send_inner(json_message, &SendOptions::default()).await?;
+        Ok(SendOutcome { deduplicated: body.is_none(), ..self.send_outcome() })
+    }
+
+    /// Direct send JSON message, returns response body, e.g. for logging or correlating responses
+    /// 
+    /// `errcode` is checked the same as `send`
+    pub async fn send_raw(&self, json_message: &str) -> XResult<String> {
+        let body = self.send_inner(json_message, &SendOptions::default()).await?;
+        Ok(body.unwrap_or_else(|| DEDUPLICATED_RESPONSE.into()))
+    }
+
+    /// Direct send JSON message, returns parsed `SendResponse`
+    /// 
+    /// Unlike `send`, a response body which is not JSON returns `DingTalkError::UnexpectedResponse`
+    pub async fn send_with_response(&self, json_message: &str) -> XResult<SendResponse> {
+        SendResponse::parse(&self.send_raw(json_message).await?)
+    }
+
+    /// Direct send JSON message, returns `SendMetrics`, e.g. for SLO dashboards
+    pub async fn send_with_metrics(&self, json_message: &str) -> XResult<SendMetrics> {
+        let stopwatch = Stopwatch::start();
+        let attempts = AtomicU32::new(0);
+        let status = self.send_inner_counted(json_message, &SendOptions::default(), &attempts).await?
+            .and_then(|sent| sent.status);
+        Ok(SendMetrics {
+            attempts: if status.is_some() { attempts.load(Ordering::Relaxed) } else { 0 },
+            elapsed: stopwatch.elapsed(),
+            status,
+        })
+    }
+
+    /// Send with retries, returns response body, `None` when message is deduplicated
+    async fn send_inner(&self, json_message: &str, options: &SendOptions) -> XResult<Option<String>> {
+        let sent = self.send_inner_counted(json_message, options, &AtomicU32::new(0)).await?;
+        Ok(sent.map(|sent| sent.body))
+    }
+
+    /// Send with retries, counts attempts in `attempts`
+    async fn send_inner_counted(&self, json_message: &str, options: &SendOptions, attempts: &AtomicU32) -> XResult<Option<Sent>> {
+        let access_token = options.access_token.as_deref().unwrap_or(&self.access_token);
+        if self.is_duplicate(access_token, json_message) {
+            return Ok(None);
+        }
+        let send = self.cancellable(self.with_retry(|| {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            self.send_once(json_message, options)
+        }));
+        #[cfg(feature = "tracing")]
+        let send = tracing::Instrument::instrument(send, self.send_span());
+        let sent = send.await?;
+        self.record_sent(access_token, json_message);
+        Ok(Some(sent))
+    }
+
+    async fn send_once(&self, json_message: &str, options: &SendOptions) -> XResult<Sent> {
+        let access_token = options.access_token.as_deref().unwrap_or(&self.access_token);
+        let signed_url = self.generate_signed_url_with_token(access_token)?;
+        if self.skip_send(json_message) {
+            return Ok(Sent { status: None, body: DRY_RUN_RESPONSE.into() });
+        }
+        let sent = match &self.transport {
+            // `Transport::post` returns body only when status is 200
+            Some(transport) => {
+                let headers = build_headers(&self.headers, options)?;
+                Sent { status: Some(200), body: transport.post(&signed_url, &headers, json_message).await? }
+            },
+            None => {
+                let response = self.post(&signed_url, json_message, options).await?;
+                Sent { status: Some(response.status().as_u16()), body: response.text().await? }
+            },
+        };
+        self.check_response(&sent.body)?;
+        Ok(sent)
+    }
+
+    /// Abort `send` with `DingTalkError::Cancelled` when cancellation token is cancelled
+    #[cfg(feature = "cancellation")]
+    pub(crate) async fn cancellable<T>(&self, send: impl Future<Output = XResult<T>>) -> XResult<T> {
+        let cancellation_token = match &self.cancellation_token {
+            Some(cancellation_token) => cancellation_token,
+            None => return send.await,
+        };
+        if cancellation_token.is_cancelled() {
+            return Err(DingTalkError::Cancelled);
+        }
+        futures::pin_mut!(send);
+        let cancelled = cancellation_token.cancelled();
+        futures::pin_mut!(cancelled);
+        match futures::future::select(send, cancelled).await {
+            futures::future::Either::Left((result, _)) => result,
+            futures::future::Either::Right(_) => Err(DingTalkError::Cancelled),
+        }
+    }
+
+    #[cfg(not(feature = "cancellation"))]
+    pub(crate) async fn cancellable<T>(&self, send: impl Future<Output = XResult<T>>) -> XResult<T> {
+        send.await
+    }
+
+    pub(crate) fn send_outcome(&self) -> SendOutcome {
+        SendOutcome {
+            signed: self.direct_url.is_empty() && !self.sec_token.is_empty(),
+            host: self.webhook_host(),
+            deduplicated: false,
+        }
+    }
+
+    /// Host of webhook URL, token is not included
+    fn webhook_host(&self) -> String {
+        self.parsed_webhook_url().and_then(|u| u.host_str().map(|h| h.to_owned())).unwrap_or_default()
+    }
+
+    fn parsed_webhook_url(&self) -> Option<reqwest::Url> {
+        reqwest::Url::parse(self.webhook_url()).ok()
+    }
+
+    /// Span of one send(with retries), webhook URL is redacted
+    #[cfg(feature = "tracing")]
+    pub(crate) fn send_span(&self) -> tracing::Span {
+        let webhook = self.parsed_webhook_url().map(|u| redact_url(&u)).unwrap_or_default();
+        tracing::info_span!("dingtalk_send", webhook = %webhook, dingtalk_type = ?self.dingtalk_type)
+    }
+
+    /// Direct send JSON message, only checks HTTP status
+    /// 
+    /// Returns as soon as the response status is received, the response body is never read,
+    /// so DingTalk `errcode` is NOT checked, a message rejected by DingTalk may be reported as success.
+    /// Use this only when latency matters more than delivery correctness.
+    pub async fn send_fast(&self, json_message: &str) -> XResult<()> {
+        if self.is_duplicate(&self.access_token, json_message) {
+            return Ok(());
+        }
+        let options = SendOptions::default();
+        let send = self.cancellable(self.with_retry(|| async {
+            let signed_url = self.generate_signed_url()?;
+            if self.skip_send(json_message) {
+                return Ok(());
+            }
+            match &self.transport {
+                Some(transport) => {
+                    let headers = build_headers(&self.headers, &options)?;
+                    transport.post(&signed_url, &headers, json_message).await.map(|_| ())
+                },
+                None => self.post(&signed_url, json_message, &options).await.map(|_| ()),
+            }
+        }));
+        #[cfg(feature = "tracing")]
+        let send = tracing::Instrument::instrument(send, self.send_span());
+        send.await?;
+        self.record_sent(&self.access_token, json_message);
+        Ok(())
+    }
+
+    /// Is duplicate of a message sent to robot of `access_token` within dedup window, logs skipped
+    pub(crate) fn is_duplicate(&self, access_token: &str, json_message: &str) -> bool {
+        let deduplicated = self.deduplicator.as_ref()
+            .map(|d| d.is_duplicate(&self.dedup_robot(access_token), json_message)).unwrap_or(false);
+        if deduplicated {
+            trace_event!(info, "duplicate DingTalk message is not sent");
+        }
+        deduplicated
+    }
+
+    /// Record sent message to robot of `access_token` for dedup
+    pub(crate) fn record_sent(&self, access_token: &str, json_message: &str) {
+        if let Some(deduplicator) = &self.deduplicator {
+            deduplicator.record(&self.dedup_robot(access_token), json_message);
+        }
+    }
+
+    /// Dedup key of robot, webhook URL and access token, never logged
+    fn dedup_robot(&self, access_token: &str) -> String {
+        format!("{}\n{}", self.webhook_url(), access_token)
+    }
+
+    /// Skip sending in dry run, logs JSON message
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn skip_send(&self, json_message: &str) -> bool {
+        if self.dry_run {
+            trace_event!(info, message = %json_message, "dry run, DingTalk message is not sent");
+        }
+        self.dry_run
+    }
+
+    /// Run `attempt` with rate limit, retry retryable errors by `retry_policy`
+    async fn with_retry<T, F, Fut>(&self, attempt: F) -> XResult<T>
+            where F: Fn() -> Fut, Fut: Future<Output = XResult<T>> {
+        let mut retries = 0;
+        loop {
+            self.acquire_rate_limit().await?;
+            let err = match attempt().await { Ok(t) => return Ok(t), Err(e) => e, };
+            match self.retry_delay(retries, &err) {
+                Some(delay) => {
+                    trace_event!(info, retry = retries + 1, delay_ms = delay.as_millis() as u64, error = %err, "retry DingTalk send");
+                    self.sleep(delay).await
+                },
+                None => return Err(err),
+            }
+            retries += 1;
+        }
+    }
+
+    /// Delay before retry `retries`, `None` when `err` should not be retried
+    pub(crate) fn retry_delay(&self, retries: u32, err: &DingTalkError) -> Option<Duration> {
+        if cfg!(target_arch = "wasm32") && self.sleep.is_none() {
+            return None;
+        }
+        let retry_policy = match &self.retry_policy {
+            Some(p) if retries < p.max_retries() && err.is_retryable() && p.try_acquire_budget() => p,
+            _ => return None,
+        };
+        Some(match err {
+            DingTalkError::RateLimited { retry_after: Some(retry_after) } => (*retry_after).max(retry_policy.backoff(retries)),
+            _ => retry_policy.backoff(retries),
+        })
+    }
+
+    /// Wait until rate limiter allows sending, must be called before signing, or the timestamp may expire
+    /// 
+    /// Client side `RateLimited` is not retried, or fail fast limiter would wait anyway
+    async fn acquire_rate_limit(&self) -> XResult<()> {
+        while let Some(wait) = self.try_acquire_rate_limit()? {
+            self.sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Take one token from rate limiter, returns `Some(wait)` when should wait and try again
+    pub(crate) fn try_acquire_rate_limit(&self) -> XResult<Option<Duration>> {
+        let rate_limiter = match &self.rate_limiter { Some(r) => r, None => return Ok(None), };
+        match rate_limiter.try_acquire() {
+            Ok(()) => Ok(None),
+            Err(wait) if rate_limiter.is_fail_fast() => Err(DingTalkError::RateLimited { retry_after: Some(wait) }),
+            Err(wait) => Ok(Some(wait)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn sleep(&self, duration: Duration) {
+        match &self.sleep {
+            Some(sleep) => sleep.sleep(duration).await,
+            None => crate::DefaultSleep.sleep(duration).await,
+        }
+    }
+
+    /// Never waits without `sleep`, there is no rate limiter and no retry without `sleep` in wasm
+    #[cfg(target_arch = "wasm32")]
+    async fn sleep(&self, duration: Duration) {
+        if let Some(sleep) = &self.sleep {
+            sleep.sleep(duration).await;
+        }
+    }
+
+    /// POST JSON message, returns response when HTTP status is 200
+    async fn post(&self, signed_url: &str, json_message: &str, options: &SendOptions) -> XResult<reqwest::Response> {
+        let request = self.client().post(signed_url)
+              .headers(build_headers(&self.headers, options)?)
+              .body(json_message.as_bytes().to_vec());
+        // browser fetch has no timeout
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = match self.timeout { Some(timeout) => request.timeout(timeout), None => request, };
+        trace_event!(debug, body_length = json_message.len(), "POST DingTalk message");
+        let response = request.send().await?;
+        trace_event!(debug, status = response.status().as_u16(), "DingTalk response");
+        check_status(response.status().as_u16(), response.headers())?;
+        Ok(response)
+    }
+
+    /// Check DingTalk response body, non-zero `errcode` is `DingTalkError::Api` error,
+    /// errcode 130101(send too fast) is `DingTalkError::RateLimited` with 60 seconds cooldown
+    pub(crate) fn check_response(&self, body: &str) -> XResult<()> {
+        let response: InnerResponse = match serde_json::from_str(body) {
+            Ok(r) => r, Err(_) => return Ok(()),
+        };
+        let errcode = parse_errcode(&response.errcode)?;
+        if errcode == ERRCODE_SECURITY_FAILED && self.sec_token.is_empty() {
+            let errmsg = response.errmsg.to_lowercase();
+            if errmsg.contains("sign") || errmsg.contains("timestamp") || errmsg.contains("签名") {
+                return Err(DingTalkError::SignatureRequired(response.errmsg));
+            }
+        }
+        if errcode == ERRCODE_SEND_TOO_FAST {
+            trace_event!(warn, errcode, errmsg = %response.errmsg, "DingTalk robot is throttled");
+            return Err(DingTalkError::RateLimited { retry_after: Some(SEND_TOO_FAST_COOLDOWN) });
+        }
+        if errcode != 0 {
+            trace_event!(warn, errcode, errmsg = %response.errmsg, "DingTalk API error");
+            return Err(DingTalkError::Api { code: errcode, message: response.errmsg });
+        }
+        Ok(())
+    }
+}
+
+/// Request headers, `Content-Type` is overridden by `dingtalk_headers`, both are overridden by per send headers
+pub(crate) fn build_headers(dingtalk_headers: &HeaderMap, options: &SendOptions) -> XResult<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_JSON_UTF8));
+    for (header_name, header_value) in dingtalk_headers {
+        headers.insert(header_name, header_value.clone());
+    }
+    for (name, value) in &options.headers {
+        let (header_name, header_value) = match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(n), Ok(v)) => (n, v),
+            _ => return Err(DingTalkError::InvalidConfig(format!("invalid header: {}: {}", name, value))),
+        };
+        headers.insert(header_name, header_value);
+    }
+    Ok(headers)
+}
+
+/// Check HTTP status, only 200 is OK
+pub(crate) fn check_status(status: u16, headers: &HeaderMap) -> XResult<()> {
+    match status {
+        200_u16 => Ok(()),
+        429_u16 => {
+            let retry_after = headers.get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            Err(DingTalkError::RateLimited { retry_after })
+        },
+        status => Err(DingTalkError::Status(status)),
+    }
+}
+
+/// Parse `errcode`, number or numeric string(some gateways), absent is 0
+fn parse_errcode(errcode: &Value) -> XResult<i64> {
+    let parsed_errcode = match errcode {
+        Value::Null => Some(0),
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse::<i64>().ok(),
+        _ => None,
+    };
+    match parsed_errcode {
+        Some(errcode) => Ok(errcode),
+        None => Err(DingTalkError::UnexpectedResponse(format!("invalid errcode: {}", errcode))),
+    }
+}
+
+/// Parse `Retry-After` header, delay-seconds or HTTP-date
+fn parse_retry_after(retry_after: &str) -> Option<Duration> {
+    let retry_after = retry_after.trim();
+    if let Ok(seconds) = retry_after.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(retry_after).ok()?;
+    Some(date.duration_since(SystemClock.now()).unwrap_or_default())
+}
@@ -0,0 +1,195 @@
+#![allow(dead_code)]
+use std::{
+    io::{ Read, Write },
+    net::{ TcpListener, TcpStream },
+    sync::{ Arc, Mutex, atomic::{ AtomicUsize, Ordering } },
+    thread,
+    time::Duration,
+};
+use serde_json::Value;
+
+/// Mock HTTP response
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub delay: Duration,
+    /// `Content-Length` sent to client, body is truncated when larger than body length
+    pub content_length: Option<usize>,
+}
+
+impl MockResponse {
+    /// 200 with `{"errcode":0,"errmsg":"ok"}`
+    pub fn ok() -> Self {
+        Self::new(200, r#"{"errcode":0,"errmsg":"ok"}"#)
+    }
+
+    pub fn new(status: u16, body: &str) -> Self {
+        MockResponse {
+            status,
+            headers: vec![],
+            body: body.into(),
+            delay: Duration::from_millis(0),
+            content_length: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn content_length(mut self, content_length: usize) -> Self {
+        self.content_length = Some(content_length);
+        self
+    }
+}
+
+/// Request received by mock server
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    pub fn query(&self, name: &str) -> Option<String> {
+        let query = self.path.split('?').nth(1)?;
+        query.split('&').find_map(|kv| {
+            let mut kv = kv.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) if k == name => Some(v.to_owned()),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// Mock HTTP server, responses are used in order, the last one is repeated
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    in_flight: Arc<InFlight>,
+}
+
+/// Requests being handled, and the max of it
+#[derive(Default)]
+struct InFlight {
+    current: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let index = Arc::new(AtomicUsize::new(0));
+        let server_requests = requests.clone();
+        let in_flight = Arc::new(InFlight::default());
+        let server_in_flight = in_flight.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream { Ok(s) => s, Err(_) => continue, };
+                let i = index.fetch_add(1, Ordering::SeqCst);
+                let response = responses[i.min(responses.len() - 1)].clone();
+                let requests = server_requests.clone();
+                let in_flight = server_in_flight.clone();
+                thread::spawn(move || {
+                    let current = in_flight.current.fetch_add(1, Ordering::SeqCst) + 1;
+                    in_flight.max.fetch_max(current, Ordering::SeqCst);
+                    handle(stream, response, requests);
+                    in_flight.current.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        MockServer { url, requests, in_flight }
+    }
+
+    /// Max requests handled at the same time
+    pub fn max_in_flight(&self) -> usize {
+        self.in_flight.max.load(Ordering::SeqCst)
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn handle(mut stream: TcpStream, response: MockResponse, requests: Arc<Mutex<Vec<MockRequest>>>) {
+    let request = match read_request(&mut stream) { Some(r) => r, None => return, };
+    requests.lock().unwrap().push(request);
+    thread::sleep(response.delay);
+
+    let content_length = response.content_length.unwrap_or(response.body.len());
+    let mut head = format!("HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n", response.status, content_length);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(response.body.as_bytes());
+    let _ = stream.flush();
+    if content_length > response.body.len() {
+        // hold the connection, body never completes
+        thread::sleep(Duration::from_secs(30));
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut buf = vec![];
+    let mut chunk = [0_u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_owned();
+    let path = request_line.next()?.to_owned();
+    let headers: Vec<(String, String)> = lines.filter_map(|l| {
+        let mut kv = l.splitn(2, ':');
+        Some((kv.next()?.trim().to_owned(), kv.next()?.trim().to_owned()))
+    }).collect();
+    let content_length = headers.iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = buf[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Some(MockRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
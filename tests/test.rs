@@ -1,6 +1,8 @@
 use dingtalk::*;
 
+/// Sends to real robot, requires token file, run with `cargo test -- --ignored`
 #[test]
+#[ignore]
 fn run_all_tests() {
     tokio_test::block_on(_test_send()).unwrap();
 }
@@ -30,7 +32,7 @@ async fn _test_send() -> XResult<()> {
     ).await?;
 
     dt.send_message(DingTalkMessage::new_action_card("action card 001", "action card text 001")
-        .set_action_card_signle_btn(DingTalkMessageActionCardBtn{
+        .single_button(DingTalkMessageActionCardBtn{
             title: "test signle btn title".into(),
             action_url: "https://hatter.ink/".into(),
         })
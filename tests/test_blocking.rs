@@ -0,0 +1,87 @@
+#![cfg(feature = "blocking")]
+mod common;
+
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_blocking_send_text() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.blocking().send_text("hello").unwrap();
+
+    let requests = server.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!("hello", requests[0].json()["text"]["content"]);
+}
+
+#[test]
+fn test_blocking_send_retry_and_api_error() {
+    let server = MockServer::start(vec![
+        MockResponse::new(500, ""),
+        MockResponse::new(200, r#"{"errcode":310000,"errmsg":"keywords not in content"}"#),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_retries(1, std::time::Duration::from_millis(1));
+    let err = dt.blocking().send_markdown("title", "text").unwrap_err();
+
+    assert!(matches!(err, DingTalkError::Api { code: 310000, .. }), "{}", err);
+    assert_eq!(2, server.requests().len());
+}
+
+#[test]
+fn test_blocking_send_dedup() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_dedup(std::time::Duration::from_secs(60));
+    assert!(!dt.blocking().send_with_outcome("{}").unwrap().deduplicated);
+    assert!(dt.blocking().send_with_outcome("{}").unwrap().deduplicated);
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_blocking_send_at_all() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.blocking().send_text_at_all("hello").unwrap();
+    dt.blocking().send_markdown_at_all("title", "text").unwrap();
+    assert!(server.requests().iter().all(|r| r.json()["at"]["isAtAll"] == true));
+}
+
+#[test]
+fn test_blocking_send_with_transport() {
+    let transport = std::sync::Arc::new(MockTransport::new());
+    let dt = DingTalk::new("abc", "").with_transport(transport.clone());
+    dt.blocking().send_text("hello").unwrap();
+    assert_eq!(1, transport.requests().len());
+    assert!(transport.sent_messages()[0].contains("hello"));
+}
+
+#[test]
+fn test_blocking_send_with_proxy() {
+    let proxy = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder()
+        .access_token("abc")
+        .webhook_url("http://dingtalk.invalid/robot/send")
+        .allow_insecure()
+        .with_proxy(&proxy.url)
+        .build().unwrap();
+    dt.blocking().send_text("via proxy").unwrap();
+    let requests = proxy.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!("http://dingtalk.invalid/robot/send?access_token=abc", requests[0].path);
+}
+
+#[test]
+fn test_blocking_send_with_custom_client() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure().client(reqwest::Client::new()).build().unwrap();
+    match dt.blocking().send_text("hello") {
+        Err(DingTalkError::InvalidConfig(message)) => assert!(message.contains("blocking_client"), "{}", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(server.requests().is_empty());
+
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure()
+        .client(reqwest::Client::new()).blocking_client(reqwest::blocking::Client::new()).build().unwrap();
+    dt.blocking().send_text("hello").unwrap();
+    assert_eq!(1, server.requests().len());
+}
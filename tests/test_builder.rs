@@ -0,0 +1,217 @@
+mod common;
+
+use std::time::Duration;
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_builder_default_webhook_url() {
+    let dt = DingTalk::builder().access_token("abc").build().unwrap();
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc", dt.generate_signed_url().unwrap());
+
+    let dt = DingTalk::builder().dingtalk_type(DingTalkType::WeChatWork).access_token("abc").build().unwrap();
+    assert_eq!("https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key=abc", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_builder_all_options() {
+    let server = MockServer::start(vec![MockResponse::new(500, ""), MockResponse::ok()]);
+    let dt = DingTalk::builder()
+        .access_token("abc")
+        .sec_token("sec")
+        .webhook_url(&server.url).allow_insecure()
+        .rate_limit(20)
+        .retries(1, Duration::from_millis(1))
+        .proxy(reqwest::Proxy::custom(|_| None::<reqwest::Url>))
+        .build().unwrap();
+    let outcome = tokio_test::block_on(dt.send_with_outcome("{}")).unwrap();
+
+    assert!(outcome.signed);
+    let requests = server.requests();
+    assert_eq!(2, requests.len());
+    assert_eq!(Some("abc".to_string()), requests[1].query("access_token"));
+}
+
+#[test]
+fn test_builder_with_proxy() {
+    let proxy = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder()
+        .access_token("abc")
+        .webhook_url("http://dingtalk.invalid/robot/send")
+        .allow_insecure()
+        .with_proxy(&proxy.url)
+        .build().unwrap();
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+
+    let requests = proxy.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!("http://dingtalk.invalid/robot/send?access_token=abc", requests[0].path);
+}
+
+#[test]
+fn test_builder_with_invalid_proxy() {
+    let err = DingTalk::builder().access_token("abc").with_proxy("not a url").build().err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.contains("proxy")), "{}", err);
+}
+
+#[test]
+fn test_builder_missing_access_token() {
+    match DingTalk::builder().sec_token("sec").build() {
+        Err(DingTalkError::InvalidConfig(message)) => assert_eq!("missing access_token", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(DingTalk::builder().direct_url("https://example.com/reply").build().is_ok());
+}
+
+#[test]
+fn test_builder_dry_run() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder().access_token("abc").webhook_url(&server.url).allow_insecure().dry_run(true).build().unwrap();
+    tokio_test::block_on(async {
+        dt.send_text("hello").await?;
+        dt.send_fast("{}").await?;
+        assert_eq!(0, dt.send_with_response("{}").await?.errcode);
+        Ok::<_, DingTalkError>(())
+    }).unwrap();
+    assert!(server.requests().is_empty());
+
+    // signed URL is still generated
+    let mut dt = dt.clone();
+    dt.set_sec_token("sec");
+    dt.set_clock(std::sync::Arc::new(FixedClock(std::time::UNIX_EPOCH - Duration::from_secs(1))));
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Clock(_)), "{}", err);
+}
+
+#[test]
+fn test_builder_direct_url_with_transport_options() {
+    let proxy = MockServer::start(vec![MockResponse::ok()]);
+    let direct_url = "http://dingtalk.invalid/robot/sendBySession?session=abc";
+    let dt = DingTalk::builder()
+        .direct_url(direct_url)
+        .allow_insecure()
+        .with_proxy(&proxy.url)
+        .timeout(Duration::from_secs(5))
+        .build().unwrap();
+    assert_eq!(direct_url, dt.generate_signed_url().unwrap());
+    assert_eq!(Some(Duration::from_secs(5)), dt.timeout);
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    assert_eq!(direct_url, proxy.requests()[0].path);
+
+    let server = MockServer::start(vec![MockResponse::ok().delay(Duration::from_millis(500))]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_timeout(Duration::from_millis(100));
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Timeout), "{}", err);
+}
+
+#[test]
+fn test_builder_with_header() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder()
+        .direct_url(&server.url).allow_insecure()
+        .with_header("X-Api-Key", "key123")
+        .with_header("X-Trace-Id", "trace1")
+        .build().unwrap();
+    assert!(!format!("{:?}", dt).contains("key123"), "{:?}", dt);
+    tokio_test::block_on(dt.send_with_options("{}", &SendOptions::default().header("X-Trace-Id", "trace2"))).unwrap();
+    let request = &server.requests()[0];
+    assert_eq!(Some("key123"), request.header("X-Api-Key"));
+    // per send header wins
+    assert_eq!(Some("trace2"), request.header("X-Trace-Id"));
+    assert_eq!(Some("application/json; charset=utf-8"), request.header("Content-Type"));
+}
+
+#[test]
+fn test_builder_with_content_type() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure().with_content_type("application/json").build().unwrap();
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    assert_eq!(Some("application/json"), server.requests()[0].header("Content-Type"));
+
+    for content_type in ["application/json; charset=utf-8", "Application/JSON", "application/vnd.gateway+json"].iter() {
+        assert!(DingTalk::builder().access_token("abc").with_content_type(content_type).build().is_ok(), "{}", content_type);
+    }
+    for content_type in ["text/plain", "application/xml", "application/jsonx", "application/+json", "json", ""].iter() {
+        match DingTalk::builder().access_token("abc").with_content_type(content_type).build() {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!(format!("Content-Type is not JSON media type: {}", content_type), message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    match DingTalk::builder().access_token("abc").with_content_type("application/json\n").build() {
+        Err(DingTalkError::InvalidConfig(message)) => assert!(message.starts_with("invalid Content-Type"), "{}", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_builder_with_invalid_header() {
+    let cases = [
+        (DingTalk::builder().access_token("abc").with_header("X Api Key", "key123"), "invalid header name: X Api Key"),
+        (DingTalk::builder().access_token("abc").with_header("X-Api-Key", "key\n123"), "invalid header value of: X-Api-Key"),
+        (DingTalk::builder().access_token("abc").with_header("content-type", "text/plain"), "overriding header Content-Type requires allow_content_type_override(true)"),
+    ];
+    for (builder, expected) in cases {
+        match builder.build() {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!(expected, message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder()
+        .direct_url(&server.url).allow_insecure()
+        .with_header("Content-Type", "application/json")
+        .allow_content_type_override(true)
+        .build().unwrap();
+    tokio_test::block_on(dt.send("{}")).unwrap();
+    assert_eq!(Some("application/json"), server.requests()[0].header("Content-Type"));
+}
+
+#[test]
+fn test_builder_https_only() {
+    let cases = [
+        DingTalk::builder().access_token("abc").webhook_url("http://gateway.example.com/robot/send?key=secret_key"),
+        DingTalk::builder().direct_url("http://gateway.example.com/robot/sendBySession?session=secret_session"),
+    ];
+    for builder in cases {
+        match builder.build() {
+            Err(DingTalkError::InvalidConfig(message)) => {
+                assert!(message.starts_with("webhook URL must be https"), "{}", message);
+                assert!(!message.contains("secret"), "{}", message);
+            },
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    assert!(matches!(DingTalk::from_json(r#"{"access_token": "abc", "default_webhook_url": "http://gateway.example.com/robot/send"}"#),
+        Err(DingTalkError::InvalidConfig(_))));
+
+    // constructors without `XResult` are checked when sending
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url);
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.starts_with("webhook URL must be https")), "{}", err);
+    assert_eq!(0, server.requests().len());
+    tokio_test::block_on(dt.allow_insecure().send_text("hello")).unwrap();
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_clone_dingtalk() {
+    let server = MockServer::start(vec![MockResponse::ok(), MockResponse::ok()]);
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure().rate_limit(20).dedup(Duration::from_secs(60))
+        .client(reqwest::Client::new()).build().unwrap();
+    let mut cloned = dt.clone();
+    cloned.keywords = vec!["[alert]".to_string()];
+    assert!(dt.keywords.is_empty());
+    assert!(cloned.client.is_some());
+    assert!(std::sync::Arc::ptr_eq(dt.rate_limiter.as_ref().unwrap(), cloned.rate_limiter.as_ref().unwrap()));
+    assert!(std::sync::Arc::ptr_eq(dt.deduplicator.as_ref().unwrap(), cloned.deduplicator.as_ref().unwrap()));
+
+    tokio_test::block_on(async {
+        dt.send_text("hello").await?;
+        // deduplicator is shared
+        cloned.send_text("hello").await?;
+        cloned.send_text("[alert] hello").await
+    }).unwrap();
+    assert_eq!(2, server.requests().len());
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "cancellation")]
+mod common;
+
+use std::{ thread, time::{ Duration, Instant } };
+use futures::StreamExt;
+use dingtalk::*;
+use common::*;
+
+fn build(url: &str, cancellation_token: &CancellationToken) -> DingTalkBuilder {
+    DingTalk::builder().direct_url(url).allow_insecure().cancellation_token(cancellation_token.clone())
+}
+
+#[test]
+fn test_send_cancelled() {
+    let server = MockServer::start(vec![MockResponse::ok().delay(Duration::from_millis(2000))]);
+    let cancellation_token = CancellationToken::new();
+    let dt = build(&server.url, &cancellation_token).build().unwrap();
+    let cancel = cancellation_token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        cancel.cancel();
+    });
+    let start = Instant::now();
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Cancelled), "{}", err);
+    assert!(start.elapsed() < Duration::from_millis(1500), "{:?}", start.elapsed());
+    assert!(!err.is_retryable());
+
+    // cancelled token aborts before sending
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Cancelled), "{}", err);
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_send_cancelled_or_timeout_first_wins() {
+    let server = MockServer::start(vec![
+        MockResponse::ok().delay(Duration::from_millis(1000)),
+        MockResponse::ok(),
+    ]);
+    let cancellation_token = CancellationToken::new();
+    let dt = build(&server.url, &cancellation_token).timeout(Duration::from_millis(100)).build().unwrap();
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Timeout), "{}", err);
+    // not cancelled
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+}
+
+#[test]
+fn test_send_cancelled_while_waiting_retry() {
+    let server = MockServer::start(vec![MockResponse::new(500, "error")]);
+    let cancellation_token = CancellationToken::new();
+    let dt = build(&server.url, &cancellation_token).retries(3, Duration::from_millis(2000)).build().unwrap();
+    let cancel = cancellation_token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        cancel.cancel();
+    });
+    let start = Instant::now();
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Cancelled), "{}", err);
+    assert!(start.elapsed() < Duration::from_millis(1500), "{:?}", start.elapsed());
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_send_stream_cancelled_while_waiting_messages() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let cancellation_token = CancellationToken::new();
+    let dt = build(&server.url, &cancellation_token).build().unwrap();
+    let cancel = cancellation_token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        cancel.cancel();
+    });
+    // stream never ends
+    let messages = futures::stream::once(async { DingTalkMessage::new_text("hello") }).chain(futures::stream::pending());
+    tokio_test::block_on(dt.send_stream(messages));
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_cancellation_token() {
+    let cancellation_token = CancellationToken::new();
+    let cloned = cancellation_token.clone();
+    assert!(!cloned.is_cancelled());
+    let waiting = thread::spawn(move || tokio_test::block_on(cloned.cancelled()));
+    thread::sleep(Duration::from_millis(100));
+    cancellation_token.cancel();
+    waiting.join().unwrap();
+    assert!(cancellation_token.is_cancelled());
+    // completes at once when already cancelled
+    tokio_test::block_on(cancellation_token.cancelled());
+    assert_eq!("CancellationToken { is_cancelled: true }", format!("{:?}", cancellation_token));
+}
@@ -0,0 +1,247 @@
+mod common;
+
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_from_json_invalid_field_type() {
+    let err = DingTalk::from_json(r#"{"access_token": 12345}"#).err().unwrap();
+    match &err {
+        DingTalkError::InvalidConfig(message) => {
+            assert_eq!("field `access_token` expected string, got number", message);
+        },
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_from_json_absent_field_default() {
+    let dt = DingTalk::from_json(r#"{"access_token": "abc"}"#).unwrap();
+    assert_eq!("abc", dt.access_token);
+    assert_eq!("", dt.sec_token);
+    assert_eq!("https://oapi.dingtalk.com/robot/send", dt.default_webhook_url);
+}
+
+#[test]
+fn test_from_config() {
+    let config: DingTalkConfig = serde_json::from_str(r#"{
+        "type": "wechat",
+        "access_token": "abc",
+        "webhook_params": { "token": "token" },
+        "rate_limit_per_minute": 20,
+        "max_retries": 3,
+        "timeout_ms": 1000
+    }"#).unwrap();
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(config, serde_json::from_str::<DingTalkConfig>(&json).unwrap());
+
+    let dt = DingTalk::from_config(config).unwrap();
+    assert_eq!("https://qyapi.weixin.qq.com/cgi-bin/webhook/send?token=abc", dt.generate_signed_url().unwrap());
+    assert!(dt.rate_limiter.is_some());
+    assert_eq!(Some(std::time::Duration::from_millis(1000)), dt.timeout);
+    assert_eq!(3, dt.retry_policy.unwrap().max_retries());
+}
+
+#[test]
+fn test_from_file_owns_strings() {
+    let f = std::env::temp_dir().join(format!("dingtalk-config-{}.json", std::process::id()));
+    let f = f.to_str().unwrap().to_owned();
+    std::fs::write(&f, r#"{"access_token": "abc", "sec_token": "sec"}"#).unwrap();
+    let dts: Vec<DingTalk> = (0..100).map(|_| DingTalk::from_file(&f).unwrap()).collect();
+    std::fs::remove_file(&f).unwrap();
+
+    assert!(dts.iter().all(|dt| dt.access_token == "abc" && dt.sec_token == "sec"));
+}
+
+#[test]
+fn test_from_file_json_config_fields() {
+    let f = std::env::temp_dir().join(format!("dingtalk-config-fields-{}.json", std::process::id()));
+    let f = f.to_str().unwrap().to_owned();
+    std::fs::write(&f, r#"{
+        "type": "WeCom",
+        "access_token": "abc",
+        "webhook_params": { "token": "token" },
+        "rate_limit_per_minute": 20,
+        "max_retries": 3,
+        "retry_base_delay_ms": null,
+        "timeout_ms": 1000
+    }"#).unwrap();
+    let dt = DingTalk::from_file(&f).unwrap();
+    std::fs::remove_file(&f).unwrap();
+
+    assert_eq!(DingTalkType::WeChatWork, dt.dingtalk_type);
+    assert_eq!("https://qyapi.weixin.qq.com/cgi-bin/webhook/send?token=abc", dt.generate_signed_url().unwrap());
+    assert!(dt.rate_limiter.is_some());
+    assert_eq!(Some(std::time::Duration::from_millis(1000)), dt.timeout);
+    assert_eq!(3, dt.retry_policy.unwrap().max_retries());
+}
+
+#[test]
+fn test_config_type_case_insensitive() {
+    for type_str in ["wechat", "WeChatWork", "WECOM"].iter() {
+        let config: DingTalkConfig = serde_json::from_str(&format!(r#"{{"type": "{}"}}"#, type_str)).unwrap();
+        assert_eq!(DingTalkType::WeChatWork, config.dingtalk_type);
+        let dt = DingTalk::from_json(&format!(r#"{{"type": "{}", "access_token": "abc"}}"#, type_str)).unwrap();
+        assert_eq!(DingTalkType::WeChatWork, dt.dingtalk_type);
+    }
+    assert!(serde_json::from_str::<DingTalkConfig>(r#"{"type": "slack"}"#).is_err());
+    assert!(matches!(DingTalk::from_json(r#"{"type": "slack", "access_token": "abc"}"#), Err(DingTalkError::InvalidConfig(_))));
+
+    let err = DingTalk::from_json(r#"{"access_token": "abc", "timeout_ms": "1000"}"#).err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message == "field `timeout_ms` expected unsigned integer, got string"), "{}", err);
+}
+
+#[test]
+fn test_from_env_prefixed() {
+    std::env::set_var("DINGTALK_TEST_ENV_ACCESS_TOKEN", "abc");
+    std::env::set_var("DINGTALK_TEST_ENV_SEC_TOKEN", "sec");
+    std::env::set_var("DINGTALK_TEST_ENV_WEBHOOK_URL", "https://example.com/robot/send");
+    let dt = DingTalk::from_env_prefixed("DINGTALK_TEST_ENV").unwrap();
+    assert_eq!("abc", dt.access_token);
+    assert_eq!("sec", dt.sec_token);
+    assert_eq!("https://example.com/robot/send", dt.default_webhook_url);
+
+    std::env::set_var("DINGTALK_TEST_ENV_DEFAULT_ACCESS_TOKEN", "abc");
+    let dt = DingTalk::from_env_prefixed("DINGTALK_TEST_ENV_DEFAULT").unwrap();
+    assert_eq!("", dt.sec_token);
+    assert_eq!("https://oapi.dingtalk.com/robot/send", dt.default_webhook_url);
+}
+
+#[test]
+fn test_from_env_missing_access_token() {
+    let err = DingTalk::from_env_prefixed("DINGTALK_TEST_ENV_MISSING").err().unwrap();
+    match &err {
+        DingTalkError::InvalidConfig(message) => assert!(message.contains("DINGTALK_TEST_ENV_MISSING_ACCESS_TOKEN"), "{}", message),
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_getters() {
+    let dt = DingTalk::new("abc", "sec");
+    assert_eq!("abc", dt.access_token());
+    assert_eq!("sec", dt.sec_token());
+    assert!(dt.has_sec_token());
+    assert_eq!("https://oapi.dingtalk.com/robot/send", dt.webhook_url());
+
+    let dt = DingTalk::from_url("https://oapi.dingtalk.com/robot/sendBySession?session=abc");
+    assert!(!dt.has_sec_token());
+    assert_eq!("https://oapi.dingtalk.com/robot/sendBySession?session=abc", dt.webhook_url());
+}
+
+#[test]
+fn test_to_file_round_trip() {
+    let f = std::env::temp_dir().join(format!("dingtalk-config-to-file-{}.json", std::process::id()));
+    let f = f.to_str().unwrap().to_owned();
+    let mut wechat_dt = DingTalk::new_wechat("key");
+    wechat_dt.set_default_webhook_url("https://gateway.example.com/wechat");
+    let proxy_dt = DingTalk::builder().access_token("abc").with_proxy("http://proxy.example.com:8080").build().unwrap();
+    let mut webhook_params_dt = DingTalk::new("abc", "sec");
+    webhook_params_dt.set_webhook_params(DingTalkWebhookParams { token: Some("token".into()), ..Default::default() });
+    let cases = [DingTalk::new("abc", "sec"), DingTalk::from_url("https://example.com/direct"), wechat_dt, proxy_dt, webhook_params_dt];
+    for dt in cases.iter() {
+        dt.to_file(&f).unwrap();
+        let read_dt = DingTalk::from_file(&f).unwrap();
+        assert_eq!(dt.dingtalk_type, read_dt.dingtalk_type);
+        assert_eq!(dt.default_webhook_url, read_dt.default_webhook_url);
+        assert_eq!(dt.access_token, read_dt.access_token);
+        assert_eq!(dt.sec_token, read_dt.sec_token);
+        assert_eq!(dt.direct_url, read_dt.direct_url);
+        assert_eq!(dt.webhook_params, read_dt.webhook_params);
+        assert_eq!(dt.proxy_url, read_dt.proxy_url);
+        assert_eq!(dt.to_json(), read_dt.to_json());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(0o600, std::fs::metadata(&f).unwrap().permissions().mode() & 0o777);
+    }
+    std::fs::remove_file(&f).unwrap();
+}
+
+#[test]
+fn test_debug_masks_secrets() {
+    let mut dt = DingTalk::new("abcdef0123456789", "SECabcdef0123456789");
+    dt.set_dead_letter(DingTalk::from_url("https://oapi.dingtalk.com/robot/sendBySession?session=secret_session"));
+    let debug = format!("{:?}", dt);
+    assert!(debug.contains(r#"access_token: "ab***""#), "{}", debug);
+    assert!(debug.contains(r#"sec_token: "SE***""#), "{}", debug);
+    assert!(debug.contains("https://oapi.dingtalk.com/robot/send"), "{}", debug);
+    assert!(!debug.contains("abcdef0123456789"), "{}", debug);
+    assert!(!debug.contains("secret_session"), "{}", debug);
+
+    let debug = format!("{:?}", DingTalk::new("abc", ""));
+    assert!(debug.contains(r#"access_token: "***", sec_token: """#), "{}", debug);
+}
+
+#[test]
+fn test_from_file_bom_and_invalid_content() {
+    let f = std::env::temp_dir().join(format!("dingtalk-config-bom-{}.json", std::process::id()));
+    let f = f.to_str().unwrap().to_owned();
+    let cases: [(&[u8], Option<&str>); 4] = [
+        (b"\xEF\xBB\xBF{\"access_token\": \"abc\"}", None),
+        (b"{\"access_token\": \"\xff\xfe\"}", Some("is not UTF-8")),
+        (b"{\"access_token\": ", Some("is not valid JSON")),
+        (b"[\"secret_token\"]", Some("is not JSON object")),
+    ];
+    for (content, expected_error) in cases.iter() {
+        std::fs::write(&f, content).unwrap();
+        let result = DingTalk::from_file(&f);
+        match (result, expected_error) {
+            (Ok(dt), None) => assert_eq!("abc", dt.access_token),
+            (Err(DingTalkError::InvalidConfig(message)), Some(expected_error)) => {
+                assert!(message.contains(&f), "{}", message);
+                assert!(message.contains(expected_error), "{}", message);
+                assert!(!message.contains("secret_token"), "{}", message);
+            },
+            (r, _) => panic!("unexpected result: {:?}", r.map(|dt| dt.access_token)),
+        }
+    }
+    std::fs::remove_file(&f).unwrap();
+}
+
+#[test]
+fn test_from_json_missing_access_token() {
+    let cases = [
+        r#"{"sec_token": "sec"}"#,
+        r#"{"type": "wechat", "access_token": ""}"#,
+    ];
+    for json in cases.iter() {
+        match DingTalk::from_json(json) {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!("missing access_token", message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    let config: DingTalkConfig = serde_json::from_str(r#"{"type": "wechat"}"#).unwrap();
+    assert!(matches!(DingTalk::from_config(config), Err(DingTalkError::InvalidConfig(_))));
+
+    // direct URL has no token
+    let dt = DingTalk::from_json(r#"{"direct_url": "https://example.com/reply"}"#).unwrap();
+    assert_eq!("https://example.com/reply", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_from_config_proxy() {
+    let proxy = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_json(&format!(r#"{{
+        "access_token": "abc",
+        "default_webhook_url": "https://dingtalk.invalid/robot/send",
+        "proxy": "{}"
+    }}"#, proxy.url)).unwrap();
+    // https is tunneled with CONNECT, mock proxy only sees the tunnel request
+    let _ = tokio_test::block_on(dt.send_text("via proxy"));
+    let requests = proxy.requests();
+    assert_eq!(1, requests.len());
+    assert!(requests[0].path.contains("dingtalk.invalid"), "{}", requests[0].path);
+
+    let config: DingTalkConfig = serde_json::from_str(&format!(r#"{{"access_token": "abc", "proxy": "{}"}}"#, proxy.url)).unwrap();
+    assert_eq!(Some(proxy.url.clone()), config.proxy);
+    let dt = DingTalk::from_config(config).unwrap();
+    let _ = tokio_test::block_on(dt.send_text("via proxy"));
+    assert_eq!(2, proxy.requests().len());
+
+    std::env::set_var("DINGTALK_TEST_ENV_PROXY_ACCESS_TOKEN", "abc");
+    std::env::set_var("DINGTALK_TEST_ENV_PROXY_PROXY", "not a url");
+    let err = DingTalk::from_env_prefixed("DINGTALK_TEST_ENV_PROXY").err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.contains("proxy")), "{}", err);
+}
@@ -0,0 +1,46 @@
+use dingtalk::*;
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_from_toml() {
+    let dt = DingTalk::from_toml("type = \"wechat\"\naccess_token = \"abc\"\ntimeout_ms = 1000\n").unwrap();
+    assert_eq!(DingTalkType::WeChatWork, dt.dingtalk_type);
+    assert_eq!("abc", dt.access_token);
+    assert_eq!(Some(std::time::Duration::from_millis(1000)), dt.timeout);
+
+    let err = DingTalk::from_toml("access_token = ").err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.contains("TOML")), "{}", err);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_from_yaml() {
+    let dt = DingTalk::from_yaml("access_token: abc\nsec_token: sec\n").unwrap();
+    assert_eq!("abc", dt.access_token);
+    assert_eq!("sec", dt.sec_token);
+
+    let err = DingTalk::from_yaml("access_token: [").err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.contains("YAML")), "{}", err);
+}
+
+#[test]
+fn test_from_file_by_extension() {
+    // (extension, content, format feature enabled)
+    let cases = [
+        ("json", r#"{"access_token": "abc", "sec_token": "sec"}"#, true),
+        ("toml", "access_token = \"abc\"\nsec_token = \"sec\"\n", cfg!(feature = "toml")),
+        ("yaml", "access_token: abc\nsec_token: sec\n", cfg!(feature = "yaml")),
+        ("yml", "access_token: abc\nsec_token: sec\n", cfg!(feature = "yaml")),
+    ];
+    for (extension, content, _) in cases.iter().filter(|(_, _, enabled)| *enabled) {
+        let f = std::env::temp_dir().join(format!("dingtalk-config-format-{}.{}", std::process::id(), extension));
+        let f = f.to_str().unwrap().to_owned();
+        std::fs::write(&f, content).unwrap();
+        let dt = DingTalk::from_file(&f);
+        std::fs::remove_file(&f).unwrap();
+
+        let dt = dt.unwrap();
+        assert_eq!("abc", dt.access_token, "{}", extension);
+        assert_eq!("sec", dt.sec_token, "{}", extension);
+    }
+}
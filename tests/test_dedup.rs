@@ -0,0 +1,79 @@
+mod common;
+
+use std::{ sync::Arc, thread, time::Duration };
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_deduplicator() {
+    let deduplicator = Deduplicator::new(Duration::from_millis(200)).max_entries(2);
+    assert!(!deduplicator.is_duplicate("robot", "a"));
+    deduplicator.record("robot", "a");
+    assert!(deduplicator.is_duplicate("robot", "a"));
+    assert!(!deduplicator.is_duplicate("robot", "b"));
+    // another robot
+    assert!(!deduplicator.is_duplicate("robot2", "a"));
+
+    // bounded, the oldest is evicted
+    deduplicator.record("robot", "b");
+    deduplicator.record("robot", "c");
+    assert!(!deduplicator.is_duplicate("robot", "a"));
+    assert!(deduplicator.is_duplicate("robot", "b"));
+    assert!(deduplicator.is_duplicate("robot", "c"));
+
+    // expired after window
+    thread::sleep(Duration::from_millis(300));
+    assert!(!deduplicator.is_duplicate("robot", "b"));
+    assert!(!deduplicator.is_duplicate("robot", "c"));
+}
+
+#[test]
+fn test_send_dedup() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_dedup(Duration::from_secs(60));
+    let outcome = tokio_test::block_on(dt.send_with_outcome(r#"{"text":"disk full"}"#)).unwrap();
+    assert!(!outcome.deduplicated);
+    let outcome = tokio_test::block_on(dt.send_with_outcome(r#"{"text":"disk full"}"#)).unwrap();
+    assert!(outcome.deduplicated);
+    // cloned `DingTalk` shares deduplicator
+    tokio_test::block_on(dt.clone().send_text("disk full")).unwrap();
+    tokio_test::block_on(dt.send_text("disk full")).unwrap();
+    assert_eq!(r#"{"errcode":0,"errmsg":"deduplicated"}"#, tokio_test::block_on(dt.send_raw(r#"{"text":"disk full"}"#)).unwrap());
+
+    let bodies: Vec<_> = server.requests().iter().map(|r| r.body.clone()).collect();
+    assert_eq!(2, bodies.len(), "{:?}", bodies);
+}
+
+#[test]
+fn test_send_dedup_failed_not_recorded() {
+    let server = MockServer::start(vec![MockResponse::new(500, "error"), MockResponse::ok()]);
+    let deduplicator = Arc::new(Deduplicator::new(Duration::from_secs(60)));
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure().deduplicator(deduplicator.clone()).build().unwrap();
+    assert!(tokio_test::block_on(dt.send_text("disk full")).is_err());
+    // failed message is sent again
+    tokio_test::block_on(dt.send_text("disk full")).unwrap();
+    // sent one is recorded
+    tokio_test::block_on(dt.send_text("disk full")).unwrap();
+    assert_eq!(2, server.requests().len());
+}
+
+#[test]
+fn test_send_dedup_per_robot() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let deduplicator = Arc::new(Deduplicator::new(Duration::from_secs(60)));
+    let dt = DingTalk::builder().access_token("tokenA").webhook_url(&server.url).allow_insecure()
+        .deduplicator(deduplicator.clone()).build().unwrap();
+    tokio_test::block_on(async {
+        dt.send_message_with_token("tokenA", DingTalkMessage::new_text("x")).await?;
+        dt.send_message_with_token("tokenB", DingTalkMessage::new_text("x")).await?;
+        // duplicate of tokenA
+        dt.send_text("x").await
+    }).unwrap();
+    // deduplicator shared with another robot
+    let other = DingTalk::builder().access_token("tokenA").webhook_url(&format!("{}/other", server.url)).allow_insecure()
+        .deduplicator(deduplicator).build().unwrap();
+    tokio_test::block_on(other.send_text("x")).unwrap();
+
+    let tokens: Vec<_> = server.requests().iter().map(|r| r.query("access_token")).collect();
+    assert_eq!(vec![Some("tokenA".to_string()), Some("tokenB".to_string()), Some("tokenA".to_string())], tokens);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use std::time::{ Duration, Instant };
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_broadcast() {
+    let server_1 = MockServer::start(vec![MockResponse::ok().delay(Duration::from_millis(300))]);
+    let server_2 = MockServer::start(vec![MockResponse::new(500, "error")]);
+    let server_3 = MockServer::start(vec![MockResponse::ok().delay(Duration::from_millis(300))]);
+    let group = DingTalkGroup::new(vec![DingTalk::from_url(&server_1.url).allow_insecure(), DingTalk::from_url(&server_2.url).allow_insecure()])
+        .add_robot(DingTalk::from_url(&server_3.url).allow_insecure());
+
+    let start = Instant::now();
+    let results = tokio_test::block_on(group.broadcast(&DingTalkMessage::new_text("alert")));
+    assert!(start.elapsed() < Duration::from_millis(550), "not concurrent: {:?}", start.elapsed());
+
+    assert_eq!(3, results.len());
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(DingTalkError::Status(500))));
+    assert!(results[2].is_ok());
+    for server in [&server_1, &server_2, &server_3].iter() {
+        assert_eq!("alert", server.requests()[0].json()["text"]["content"]);
+    }
+}
@@ -0,0 +1,53 @@
+use dingtalk::*;
+
+#[test]
+fn test_parse_incoming_message() {
+    let body = r#"{
+        "conversationId": "cid123",
+        "atUsers": [{ "dingtalkId": "$:LWCP_v1:$bot" }, { "dingtalkId": "$:LWCP_v1:$user", "staffId": "staff01" }],
+        "chatbotUserId": "$:LWCP_v1:$bot",
+        "msgId": "msg123",
+        "senderNick": "Alice",
+        "isAdmin": true,
+        "senderStaffId": "staff02",
+        "sessionWebhookExpiredTime": 1600003600000,
+        "createAt": 1600000000000,
+        "senderCorpId": "corp01",
+        "conversationType": "2",
+        "senderId": "$:LWCP_v1:$sender",
+        "conversationTitle": "Ops",
+        "isInAtList": true,
+        "sessionWebhook": "https://oapi.dingtalk.com/robot/sendBySession?session=abc",
+        "text": { "content": " status" },
+        "robotCode": "normal",
+        "msgtype": "text"
+    }"#;
+    let incoming = IncomingMessage::parse(body).unwrap();
+    assert_eq!("text", incoming.msg_type);
+    assert_eq!("status", incoming.text_content());
+    assert_eq!("msg123", incoming.msg_id);
+    assert_eq!(1600000000000, incoming.create_at);
+    assert!(incoming.is_group());
+    assert_eq!("cid123", incoming.conversation_id);
+    assert_eq!(Some("Ops".into()), incoming.conversation_title);
+    assert_eq!("Alice", incoming.sender_nick);
+    assert_eq!(Some("staff02".into()), incoming.sender_staff_id);
+    assert_eq!(2, incoming.at_users.len());
+    assert_eq!(None, incoming.at_users[0].staff_id);
+    assert_eq!(Some("staff01".into()), incoming.at_users[1].staff_id);
+    assert!(incoming.is_admin);
+    assert!(incoming.is_in_at_list);
+    assert_eq!("https://oapi.dingtalk.com/robot/sendBySession?session=abc", incoming.session_webhook);
+    assert_eq!(1600003600000, incoming.session_webhook_expired_time);
+}
+
+#[test]
+fn test_parse_incoming_message_missing_fields() {
+    let incoming = IncomingMessage::parse(r#"{ "msgtype": "text", "conversationType": "1" }"#).unwrap();
+    assert_eq!("", incoming.text_content());
+    assert!(!incoming.is_group());
+    assert!(incoming.at_users.is_empty());
+    assert_eq!(None, incoming.sender_corp_id);
+
+    assert!(matches!(IncomingMessage::parse("not json"), Err(DingTalkError::Json(_))));
+}
@@ -0,0 +1,47 @@
+use dingtalk::*;
+
+#[test]
+fn test_markdown_builder() {
+    let markdown = MarkdownBuilder::new()
+        .heading(3, "Build failed")
+        .bold("dingtalk")
+        .list_item("branch: master")
+        .list_item("commit: 9a7d431")
+        .text("see")
+        .link("Details", "https://example.com/build/1")
+        .image("graph", "https://example.com/a.png")
+        .at_mobile("13800000000")
+        .build();
+    assert_eq!("### Build failed\n\n**dingtalk**\n\n- branch: master\n- commit: 9a7d431\n\nsee\n\n\
+        [Details](https://example.com/build/1)\n\n![graph](https://example.com/a.png)\n\n@13800000000", markdown);
+}
+
+#[test]
+fn test_markdown_builder_heading_level() {
+    assert_eq!("# a", MarkdownBuilder::new().heading(0, "a").build());
+    assert_eq!("###### a", MarkdownBuilder::new().heading(7, "a").build());
+    assert_eq!("", MarkdownBuilder::new().build());
+}
+
+#[test]
+fn test_markdown_with_image() {
+    let dingtalk_message = DingTalkMessage::new_markdown_with_image("CPU", "load is high", "https://example.com/cpu.png");
+    assert_eq!("load is high\n\n![CPU](https://example.com/cpu.png)", dingtalk_message.markdown_content);
+    assert!(dingtalk_message.validate().is_ok());
+
+    let err = DingTalkMessage::new_markdown_with_image("CPU", "load is high", "http://example.com/cpu.png").validate().unwrap_err();
+    assert!(matches!(&err, DingTalkError::InvalidMessage(message) if message.contains("http://example.com/cpu.png")), "{}", err);
+    let markdown = MarkdownBuilder::new().image("a", "https://example.com/a.png").image("b", "//example.com/b.png").build();
+    assert!(DingTalkMessage::new_markdown("title", &markdown).validate().is_err());
+}
+
+#[test]
+fn test_escape_markdown() {
+    assert_eq!("plain text 1.0", escape_markdown("plain text 1.0"));
+    assert_eq!("\\*\\*bold\\*\\* \\_it\\_ \\`code\\` \\[a\\]\\(b\\) \\# \\> \\\\", escape_markdown("**bold** _it_ `code` [a](b) # > \\"));
+    assert_eq!("line1\n\\- item \\| \\~ \\! \\<br\\> \\{\\} \\+", escape_markdown("line1\n- item | ~ ! <br> {} +"));
+    assert_eq!("错误: \\_\\_init\\_\\_", escape_markdown("错误: __init__"));
+
+    let markdown = MarkdownBuilder::new().text_escaped("panicked at src/main_loop.rs").build();
+    assert_eq!("panicked at src/main\\_loop.rs", markdown);
+}
@@ -0,0 +1,237 @@
+use dingtalk::*;
+
+#[test]
+fn test_validate_action_card_text_too_large() {
+    let text = "x".repeat(5001);
+    assert!(DingTalkMessage::new_text(&text).validate().is_ok());
+
+    let err = DingTalkMessage::new_action_card("action card title", &text)
+        .single_button(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() })
+        .validate().unwrap_err();
+    assert!(err.to_string().contains("ActionCard text"), "{}", err);
+}
+
+#[test]
+fn test_validate_text_too_large() {
+    let text = "x".repeat(20001);
+    let err = DingTalkMessage::new_text(&text).validate().unwrap_err();
+    assert!(err.to_string().contains("Text content"), "{}", err);
+}
+
+#[test]
+fn test_validate_markdown_title_bytes() {
+    // 42 CJK chars are 126 bytes
+    let title = "磁盘告警".repeat(10) + "磁盘";
+    assert_eq!(42, title.chars().count());
+    assert!(DingTalkMessage::new_markdown(&format!("{}ab", title), "content").validate().is_ok());
+    match DingTalkMessage::new_markdown(&format!("{}告", title), "content").validate() {
+        Err(DingTalkError::InvalidMessage(message)) => assert_eq!("Markdown title too large: 129 bytes, max 128 bytes", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    // 128 chars are fine when ASCII
+    assert!(DingTalkMessage::new_markdown(&"x".repeat(128), "content").validate().is_ok());
+    assert!(DingTalkMessage::new_markdown(&"x".repeat(129), "content").validate().is_err());
+}
+
+#[test]
+fn test_kind() {
+    assert_eq!(DingTalkMessageType::Text, DingTalkMessage::new_text("text").kind());
+    assert_eq!(DingTalkMessageType::Markdown, DingTalkMessage::new_markdown("title", "content").kind());
+    assert_eq!(DingTalkMessageType::Link, DingTalkMessage::new_link("title", "text", "", "https://example.com/").kind());
+    assert_eq!(DingTalkMessageType::ActionCard, DingTalkMessage::new_action_card("title", "text").kind());
+    assert_eq!(DingTalkMessageType::FeedCard, DingTalkMessage::new_feed_card().kind());
+}
+
+#[test]
+fn test_is_empty() {
+    assert!(!DingTalkMessage::new_text("text").is_empty());
+    assert!(DingTalkMessage::new_text("").is_empty());
+    assert!(!DingTalkMessage::new_markdown("title", "content").is_empty());
+    assert!(DingTalkMessage::new_markdown("title", "").is_empty());
+    assert!(!DingTalkMessage::new_link("title", "text", "", "https://example.com/").is_empty());
+    assert!(DingTalkMessage::new_link("title", "text", "https://example.com/a.png", "").is_empty());
+    assert!(!DingTalkMessage::new_action_card("title", "text").is_empty());
+    assert!(DingTalkMessage::new_action_card("", "text").is_empty());
+    assert!(DingTalkMessage::new_feed_card().is_empty());
+    assert!(!DingTalkMessage::new_feed_card().add_feed_card_link_detail("title", "https://example.com/", "").is_empty());
+}
+
+#[test]
+fn test_message_serde() {
+    let dingtalk_message = DingTalkMessage::new_action_card("title", "text")
+        .add_action_card_btn(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() })
+        .at_all();
+    let json = serde_json::to_string(&dingtalk_message).unwrap();
+    let deserialized: DingTalkMessage = serde_json::from_str(&json).unwrap();
+
+    let dt = DingTalk::new("token", "");
+    assert_eq!(dt.build_message_json(&dingtalk_message).unwrap(), dt.build_message_json(&deserialized).unwrap());
+
+    let deserialized: DingTalkMessage = serde_json::from_str(r#"{"message_type": "text", "text_content": "hello"}"#).unwrap();
+    assert_eq!(DingTalkMessageType::Text, deserialized.kind());
+    assert_eq!("hello", deserialized.text_content);
+}
+
+#[test]
+fn test_message_serde_round_trip() {
+    let cases = [
+        DingTalkMessage::new_text("hello").at_mobile("13800000000").at_user_id("user001"),
+        DingTalkMessage::new_markdown("title", "# text").extra_field("newFeature", serde_json::json!({ "enabled": true })),
+        DingTalkMessage::new_link("title", "text", "https://example.com/a.png", "https://example.com/"),
+        DingTalkMessage::new_action_card("title", "text").action_card_hide_avatar().action_card_btn_landscape()
+            .single_button(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() }),
+        DingTalkMessage::new_feed_card()
+            .add_feed_card_link_detail("link1", "https://example.com/1", "https://example.com/1.png")
+            .add_feed_card_link_detail("link2", "https://example.com/2", ""),
+    ];
+    let dt = DingTalk::new("token", "");
+    for dingtalk_message in cases.iter() {
+        // e.g. queued in Redis and sent later
+        let queued = serde_json::to_vec(dingtalk_message).unwrap();
+        let deserialized: DingTalkMessage = serde_json::from_slice(&queued).unwrap();
+        assert_eq!(dingtalk_message.kind(), deserialized.kind());
+        assert_eq!(dt.build_message_json(dingtalk_message).unwrap(), dt.build_message_json(&deserialized).unwrap());
+        assert_eq!(serde_json::to_value(dingtalk_message).unwrap(), serde_json::to_value(&deserialized).unwrap());
+    }
+}
+
+#[test]
+fn test_validate_invalid_message() {
+    let btn = DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() };
+    let cases = [
+        (DingTalkMessage::new_text(""), "Text content is empty"),
+        (DingTalkMessage::new_markdown("", "content"), "Markdown title is empty"),
+        (DingTalkMessage::new_markdown("title", ""), "Markdown content is empty"),
+        (DingTalkMessage::new_action_card("title", "text"), "ActionCard has neither single btn nor btns"),
+        (DingTalkMessage::new_feed_card(), "FeedCard has no links"),
+    ];
+    for (dingtalk_message, expected) in cases.iter() {
+        match dingtalk_message.validate() {
+            Err(DingTalkError::InvalidMessage(message)) => assert_eq!(*expected, message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    assert!(DingTalkMessage::new_action_card("title", "text").single_button(btn.clone()).validate().is_ok());
+    assert!(DingTalkMessage::new_action_card("title", "text").add_action_card_btn(btn).validate().is_ok());
+}
+
+#[test]
+fn test_ensure_keyword() {
+    let keywords = vec!["[alert]".to_string()];
+    let dingtalk_message = DingTalkMessage::new_text("hello");
+    assert!(!dingtalk_message.contains_keyword(&keywords));
+    let dingtalk_message = dingtalk_message.ensure_keyword("[alert]");
+    assert_eq!("hello\n[alert]", dingtalk_message.text_content);
+    assert!(dingtalk_message.contains_keyword(&keywords));
+    assert_eq!("hello\n[alert]", dingtalk_message.ensure_keyword("[alert]").text_content);
+
+    let dingtalk_message = DingTalkMessage::new_markdown("[alert] title", "content").ensure_keyword("[alert]");
+    assert_eq!("content", dingtalk_message.markdown_content);
+    let dingtalk_message = DingTalkMessage::new_markdown("title", "content").ensure_keyword("[alert]");
+    assert_eq!("content\n\n[alert]", dingtalk_message.markdown_content);
+}
+
+#[test]
+fn test_validate_action_card_btns() {
+    let btn = DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() };
+    let mut dingtalk_message = DingTalkMessage::new_action_card("title", "text");
+    for _ in 0..5 {
+        dingtalk_message = dingtalk_message.add_action_card_btn(btn.clone());
+    }
+    assert!(dingtalk_message.validate().is_ok());
+
+    match dingtalk_message.clone().add_action_card_btn(btn.clone()).validate() {
+        Err(DingTalkError::InvalidMessage(message)) => assert_eq!("ActionCard has 6 btns, max is 5", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match dingtalk_message.single_button(btn).validate() {
+        Err(DingTalkError::InvalidMessage(message)) => assert_eq!("ActionCard has both single btn and btns", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_message_display() {
+    let btn = DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() };
+    let mobiles = vec!["13800000000".to_string()];
+    let cases = [
+        (DingTalkMessage::new_text("hello"), "Text: hello"),
+        (DingTalkMessage::new_text("hello").at_all(), "Text: hello @all"),
+        (DingTalkMessage::new_text("hello").at_mobiles(&mobiles).at_user_ids(["user1"]).at_all(), "Text: hello @all"),
+        (DingTalkMessage::new_text("hello").at_mobiles(&mobiles).at_user_ids(["user1"]), "Text: hello @13800000000 @user1"),
+        (DingTalkMessage::new_markdown("title", "# text").at_mobiles(&mobiles), "Markdown[title]: # text @13800000000"),
+        (DingTalkMessage::new_link("title", "text", "", "https://example.com/"), "Link[title]: https://example.com/"),
+        (DingTalkMessage::new_action_card("title", "text").add_action_card_btn(btn.clone()).add_action_card_btn(btn),
+            "ActionCard[title]: text (2 btns)"),
+        (DingTalkMessage::new_feed_card()
+            .add_feed_card_link_detail("link1", "https://example.com/1", "")
+            .add_feed_card_link_detail("link2", "https://example.com/2", ""), "FeedCard: link1, link2"),
+    ];
+    for (dingtalk_message, expected) in cases.iter() {
+        assert_eq!(*expected, dingtalk_message.to_string());
+    }
+}
+
+#[test]
+fn test_message_type_from_str() {
+    let cases = [
+        ("text", DingTalkMessageType::Text),
+        ("Markdown", DingTalkMessageType::Markdown),
+        ("LINK", DingTalkMessageType::Link),
+        ("actionCard", DingTalkMessageType::ActionCard),
+        ("actioncard", DingTalkMessageType::ActionCard),
+        ("feedCard", DingTalkMessageType::FeedCard),
+    ];
+    for (s, message_type) in cases.iter() {
+        assert_eq!(*message_type, s.parse::<DingTalkMessageType>().unwrap());
+        assert_eq!(*message_type, message_type.to_string().parse::<DingTalkMessageType>().unwrap());
+    }
+    assert_eq!("actionCard", DingTalkMessageType::ActionCard.as_str());
+    assert_eq!("feedCard", DingTalkMessageType::FeedCard.to_string());
+
+    for s in ["", "image", "action_card"].iter() {
+        match s.parse::<DingTalkMessageType>() {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!(format!("unknown message type: {}", s), message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn test_action_card_button_modes() {
+    let btn = DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() };
+    let single = DingTalkMessage::new_action_card("title", "text").single_button(btn.clone());
+    assert!(single.validate().is_ok());
+    assert_eq!(Some("btn"), single.action_card_single_btn.as_ref().map(|b| b.title.as_str()));
+    assert!(single.action_card_btns.is_empty());
+
+    let multiple = DingTalkMessage::new_action_card("title", "text")
+        .add_action_card_btn(btn.clone()).add_action_card_btn(btn.clone());
+    assert!(multiple.validate().is_ok());
+    assert!(multiple.action_card_single_btn.is_none());
+    assert_eq!(2, multiple.action_card_btns.len());
+
+    let mixed = DingTalkMessage::new_action_card("title", "text").add_action_card_btn(btn.clone()).single_button(btn.clone());
+    match mixed.validate() {
+        Err(DingTalkError::InvalidMessage(message)) => assert_eq!("ActionCard has both single btn and btns", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    #[allow(deprecated)]
+    let deprecated = DingTalkMessage::new_action_card("title", "text").set_action_card_signle_btn(btn);
+    assert_eq!(Some("btn"), deprecated.action_card_single_btn.as_ref().map(|b| b.title.as_str()));
+}
+
+#[test]
+fn test_clone_message_as_template() {
+    let template = DingTalkMessage::new_markdown("[alert] disk", "disk usage").at_mobiles(&["13800000000".to_string()]);
+    let mut message = template.clone();
+    message.markdown_content.push_str(" 95%");
+    let message = message.at_all();
+    assert_eq!("disk usage 95%", message.markdown_content);
+    assert!(message.at_all);
+
+    assert_eq!("disk usage", template.markdown_content);
+    assert!(!template.at_all);
+    assert_eq!(vec!["13800000000".to_string()], template.at_mobiles);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::sync::Mutex;
+use dingtalk::*;
+use common::*;
+
+#[derive(Default)]
+struct RecordNotifier {
+    texts: Mutex<Vec<String>>,
+}
+
+impl Notifier for RecordNotifier {
+    fn notify<'a>(&'a self, text: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            self.texts.lock().unwrap().push(text.into());
+            Ok(())
+        })
+    }
+}
+
+#[test]
+fn test_notifier() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let record = RecordNotifier::default();
+    let notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(DingTalk::from_url(&server.url).allow_insecure())];
+    tokio_test::block_on(async {
+        for notifier in &notifiers {
+            notifier.notify("disk full").await?;
+            notifier.notify_markdown("alert", "**disk full**").await?;
+        }
+        record.notify_markdown("alert", "**disk full**").await
+    }).unwrap();
+
+    let requests = server.requests();
+    assert_eq!("disk full", requests[0].json()["text"]["content"]);
+    assert_eq!("**disk full**", requests[1].json()["markdown"]["text"]);
+    assert_eq!(vec!["alert\n**disk full**".to_string()], *record.texts.lock().unwrap());
+}
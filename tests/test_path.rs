@@ -0,0 +1,34 @@
+use dingtalk::*;
+
+// only test of this binary, `HOME` is process wide
+#[test]
+fn test_from_file_paths() {
+    let name = format!("dingtalk-path-{}.json", std::process::id());
+    let config = r#"{"access_token": "abc"}"#;
+
+    // ~/
+    let home = std::env::temp_dir().join(format!("dingtalk-home-{}", std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    std::fs::write(home.join(&name), config).unwrap();
+    std::env::set_var("HOME", &home);
+    let dt = DingTalk::from_file(&format!("~/{}", name));
+    std::fs::remove_dir_all(&home).unwrap();
+    assert_eq!("abc", dt.unwrap().access_token);
+
+    // absolute
+    let absolute = std::env::temp_dir().join(&name);
+    std::fs::write(&absolute, config).unwrap();
+    let dt = DingTalk::from_file(absolute.to_str().unwrap());
+    std::fs::remove_file(&absolute).unwrap();
+    assert_eq!("abc", dt.unwrap().access_token);
+
+    // relative to current dir
+    let relative = name.clone();
+    std::fs::write(&relative, config).unwrap();
+    let dt = DingTalk::from_file(&relative);
+    std::fs::remove_file(&relative).unwrap();
+    assert_eq!("abc", dt.unwrap().access_token);
+
+    let err = DingTalk::from_file("~root/.dingtalk-token.json").err().unwrap();
+    assert!(matches!(&err, DingTalkError::InvalidConfig(message) if message.contains("~user")), "{}", err);
+}
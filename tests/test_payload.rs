@@ -0,0 +1,315 @@
+mod common;
+
+use std::{ sync::Arc, time::{ Duration, UNIX_EPOCH } };
+use serde_json::{ json, Value };
+use dingtalk::*;
+use common::*;
+
+fn send_and_capture(dingtalk_message: DingTalkMessage) -> Value {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    tokio_test::block_on(dt.send_message(dingtalk_message)).unwrap();
+    server.requests()[0].json()
+}
+
+#[test]
+fn test_payload_link_omit_empty_pic_url() {
+    let message_json = send_and_capture(DingTalkMessage::new_link("link title", "link text", "", "https://example.com/"));
+    assert_eq!("link title", message_json["link"]["title"]);
+    assert!(message_json["link"].get("picUrl").is_none(), "{}", message_json);
+}
+
+#[test]
+fn test_payload_action_card_omit_empty_btns() {
+    let message_json = send_and_capture(DingTalkMessage::new_action_card("title", "text")
+        .single_button(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() }));
+    assert!(message_json["actionCard"].get("btns").is_none(), "{}", message_json);
+    assert_eq!("btn", message_json["actionCard"]["singleTitle"]);
+}
+
+#[test]
+fn test_payload_at_all_omit_empty_at_mobiles() {
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello").at_all());
+    assert_eq!(Value::Bool(true), message_json["at"]["isAtAll"]);
+    assert!(message_json["at"].get("atMobiles").is_none(), "{}", message_json);
+}
+
+#[test]
+fn test_payload_at_user_ids_only() {
+    let user_ids = vec!["user001".to_string(), "user002".to_string()];
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello").at_user_ids(&user_ids));
+    assert_eq!(json!(["user001", "user002"]), message_json["at"]["atUserIds"]);
+    assert_eq!(Value::Bool(false), message_json["at"]["isAtAll"]);
+    assert!(message_json["at"].get("atMobiles").is_none(), "{}", message_json);
+}
+
+#[test]
+fn test_payload_at_mobiles_array() {
+    let mobiles = vec!["13800000000".to_string(), "13900000000".to_string()];
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello").at_mobiles(&mobiles));
+    assert_eq!(json!({
+        "atMobiles": ["13800000000", "13900000000"],
+        "isAtAll": false,
+    }), message_json["at"]);
+}
+
+#[test]
+fn test_build_message_json() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let dingtalk_message = DingTalkMessage::new_markdown("title", "# text").at_all();
+    let message_json = dt.build_message_json(&dingtalk_message).unwrap();
+    tokio_test::block_on(dt.send_message(dingtalk_message)).unwrap();
+
+    assert_eq!(message_json, server.requests()[0].body);
+    assert!(dt.build_message_json(&DingTalkMessage::new_text(&"a".repeat(20001))).is_err());
+}
+
+#[test]
+fn test_send_action_card_and_feed_card() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    tokio_test::block_on(dt.send_action_card("title", "text", vec![
+        DingTalkMessageActionCardBtn { title: "btn1".into(), action_url: "https://example.com/1".into() },
+        DingTalkMessageActionCardBtn { title: "btn2".into(), action_url: "https://example.com/2".into() },
+    ])).unwrap();
+    tokio_test::block_on(dt.send_feed_card(vec![
+        DingTalkMessageFeedCardLink { title: "link".into(), message_url: "https://example.com/".into(), pic_url: "".into() },
+    ])).unwrap();
+
+    let requests = server.requests();
+    let action_card_json = requests[0].json();
+    assert_eq!("actionCard", action_card_json["msgtype"]);
+    assert_eq!("btn2", action_card_json["actionCard"]["btns"][1]["title"]);
+    let feed_card_json = requests[1].json();
+    assert_eq!("feedCard", feed_card_json["msgtype"]);
+    assert_eq!("link", feed_card_json["feedCard"]["links"][0]["title"]);
+}
+
+#[test]
+fn test_payload_text() {
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello"));
+    assert_eq!(json!({
+        "msgtype": "text",
+        "text": { "content": "hello" },
+    }), message_json);
+}
+
+#[test]
+fn test_payload_markdown() {
+    let message_json = send_and_capture(DingTalkMessage::new_markdown("title", "# text"));
+    assert_eq!(json!({
+        "msgtype": "markdown",
+        "markdown": { "title": "title", "text": "# text" },
+    }), message_json);
+}
+
+#[test]
+fn test_payload_link() {
+    let message_json = send_and_capture(DingTalkMessage::new_link("title", "text", "https://example.com/a.png", "https://example.com/"));
+    assert_eq!(json!({
+        "msgtype": "link",
+        "link": {
+            "title": "title",
+            "text": "text",
+            "picUrl": "https://example.com/a.png",
+            "messageUrl": "https://example.com/",
+        },
+    }), message_json);
+}
+
+#[test]
+fn test_request_headers_and_signed_query() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let mut dt = DingTalk::new("abc", "SEC0123456789");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000000))));
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!("POST", request.method);
+    assert_eq!(Some("application/json; charset=utf-8"), request.header("content-type"));
+    assert_eq!(Some("abc".into()), request.query("access_token"));
+    assert_eq!(Some("1600000000000".into()), request.query("timestamp"));
+    assert_eq!(Some("nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8%3D".into()), request.query("sign"));
+}
+
+#[test]
+fn test_payload_auto_append_at() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder().webhook_url(&server.url).allow_insecure().access_token("abc").auto_append_at(true).build().unwrap();
+    let mobiles = vec!["13800000000".to_string(), "13900000000".to_string()];
+    let user_ids = vec!["user001".to_string()];
+    tokio_test::block_on(async {
+        dt.send_message(DingTalkMessage::new_text("disk full").at_mobiles(&mobiles).at_user_ids(&user_ids)).await?;
+        dt.send_message(DingTalkMessage::new_markdown("alert", "disk full @13800000000").at_mobiles(&mobiles)).await?;
+        dt.send_message(DingTalkMessage::new_text("disk full")).await
+    }).unwrap();
+
+    let requests = server.requests();
+    let text_json = requests[0].json();
+    assert_eq!("disk full\n@13800000000 @13900000000 @user001", text_json["text"]["content"]);
+    assert_eq!(json!(["13800000000", "13900000000"]), text_json["at"]["atMobiles"]);
+    assert_eq!(json!(["user001"]), text_json["at"]["atUserIds"]);
+    let markdown_json = requests[1].json();
+    assert_eq!("disk full @13800000000\n\n@13900000000", markdown_json["markdown"]["text"]);
+    assert_eq!(json!(["13800000000", "13900000000"]), markdown_json["at"]["atMobiles"]);
+    assert_eq!("disk full", requests[2].json()["text"]["content"]);
+
+    // not appended by default
+    let message_json = send_and_capture(DingTalkMessage::new_text("disk full").at_mobiles(&mobiles));
+    assert_eq!("disk full", message_json["text"]["content"]);
+}
+
+#[test]
+fn test_payload_at_all_message_types() {
+    let mobiles = vec!["13800000000".to_string()];
+    let btn = DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() };
+    let cases = [
+        DingTalkMessage::new_text("text"),
+        DingTalkMessage::new_markdown("title", "text"),
+        DingTalkMessage::new_link("title", "text", "", "https://example.com/"),
+        DingTalkMessage::new_action_card("title", "text").single_button(btn.clone()),
+        DingTalkMessage::new_action_card("title", "text").add_action_card_btn(btn.clone()).add_action_card_btn(btn),
+        DingTalkMessage::new_feed_card().add_feed_card_link_detail("title", "https://example.com/", ""),
+    ];
+    for dingtalk_message in cases.iter() {
+        let message_json = send_and_capture(dingtalk_message.clone().at_mobiles(&mobiles));
+        assert_eq!(json!({
+            "atMobiles": ["13800000000"],
+            "isAtAll": false,
+        }), message_json["at"], "{}", message_json);
+    }
+
+    let message_json = send_and_capture(DingTalkMessage::new_action_card("title", "text")
+        .single_button(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() })
+        .at_all());
+    assert_eq!("btn", message_json["actionCard"]["singleTitle"]);
+    assert_eq!(Value::Bool(true), message_json["at"]["isAtAll"]);
+}
+
+#[test]
+fn test_payload_extra_fields() {
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello")
+        .extra_field("newFeature", json!({ "enabled": true }))
+        .extra_field("msgtype", json!("text")));
+    assert_eq!(json!({
+        "msgtype": "text",
+        "text": { "content": "hello" },
+        "newFeature": { "enabled": true },
+    }), message_json);
+
+    // extra fields override built-in fields
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello").at_all()
+        .extra_field("at", json!({ "isAtAll": false })));
+    assert_eq!(json!({ "isAtAll": false }), message_json["at"]);
+
+    let mut dingtalk_message = DingTalkMessage::new_text("hello");
+    dingtalk_message.extra_fields = Some(json!(["not", "object"]));
+    let err = DingTalk::new("abc", "").build_message_json(&dingtalk_message).unwrap_err();
+    assert!(matches!(&err, DingTalkError::InvalidMessage(message) if message.starts_with("Extra fields")), "{}", err);
+}
+
+#[test]
+fn test_payload_at_all_ignores_mentions() {
+    let mobiles = vec!["13800000000".to_string()];
+    let user_ids = vec!["user001".to_string()];
+    let dingtalk_message = DingTalkMessage::new_text("hello").at_mobiles(&mobiles).at_user_ids(&user_ids).at_all();
+    let message_json = send_and_capture(dingtalk_message.clone().append_at_mentions());
+    assert_eq!(json!({ "isAtAll": true }), message_json["at"]);
+    assert_eq!("hello", message_json["text"]["content"]);
+
+    let message_json = send_and_capture(dingtalk_message.clone().clear_at());
+    assert!(message_json.get("at").is_none(), "{}", message_json);
+    let message_json = send_and_capture(dingtalk_message.clear_at().at_mobiles(&mobiles));
+    assert_eq!(json!({ "atMobiles": ["13800000000"], "isAtAll": false }), message_json["at"]);
+}
+
+#[test]
+fn test_payload_at_mobile_and_at_user_id() {
+    let message_json = send_and_capture(DingTalkMessage::new_text("hello")
+        .at_mobile("13800000000").at_mobile("13900000000")
+        .at_user_id("user001"));
+    assert_eq!(json!({
+        "atMobiles": ["13800000000", "13900000000"],
+        "atUserIds": ["user001"],
+        "isAtAll": false,
+    }), message_json["at"]);
+
+    let owners = ["user001", "user002"];
+    let dingtalk_message = DingTalkMessage::new_text("hello")
+        .at_mobiles(["13800000000"])
+        .at_mobiles(vec!["13900000000".to_string()])
+        .at_user_ids(owners.iter().filter(|o| o.ends_with('2')));
+    assert_eq!(vec!["13800000000", "13900000000"], dingtalk_message.at_mobiles);
+    assert_eq!(vec!["user002"], dingtalk_message.at_user_ids);
+}
+
+#[test]
+fn test_payload_at_mobiles_and_at_user_ids_combined() {
+    let mobiles = vec!["13800000000".to_string()];
+    let user_ids = vec!["user001".to_string(), "user002".to_string()];
+    let message_json = send_and_capture(DingTalkMessage::new_markdown("title", "text")
+        .at_mobiles(&mobiles).at_user_ids(&user_ids));
+    assert_eq!(json!({
+        "msgtype": "markdown",
+        "markdown": { "title": "title", "text": "text" },
+        "at": {
+            "atMobiles": ["13800000000"],
+            "atUserIds": ["user001", "user002"],
+            "isAtAll": false,
+        },
+    }), message_json);
+}
+
+#[test]
+fn test_payload_send_at_all() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    tokio_test::block_on(dt.send_text_at_all("disk full")).unwrap();
+    tokio_test::block_on(dt.send_markdown_at_all("alert", "# disk full")).unwrap();
+    tokio_test::block_on(dt.send_text("disk full")).unwrap();
+    let requests = server.requests();
+    assert_eq!(json!({
+        "msgtype": "text",
+        "text": { "content": "disk full" },
+        "at": { "isAtAll": true },
+    }), requests[0].json());
+    assert_eq!(json!({
+        "msgtype": "markdown",
+        "markdown": { "title": "alert", "text": "# disk full" },
+        "at": { "isAtAll": true },
+    }), requests[1].json());
+    assert!(requests[2].json().get("at").is_none(), "{}", requests[2].json());
+}
+
+#[test]
+fn test_payload_send_with_at() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let cached_json = dt.build_message_json(&DingTalkMessage::new_text("disk full").at_mobile("13700000000")).unwrap();
+    let at = AtConfig { at_all: false, mobiles: vec!["13800000000".into()], user_ids: vec!["user001".into()] };
+    tokio_test::block_on(dt.send_with_at(&cached_json, at)).unwrap();
+    tokio_test::block_on(dt.send_with_at(r#"{"msgtype":"text","text":{"content":"disk full"}}"#,
+        AtConfig { at_all: true, mobiles: vec!["13800000000".into()], ..AtConfig::default() })).unwrap();
+    // nobody is @, JSON message is unchanged
+    tokio_test::block_on(dt.send_with_at(&cached_json, AtConfig::default())).unwrap();
+
+    let requests = server.requests();
+    assert_eq!(json!({
+        "msgtype": "text",
+        "text": { "content": "disk full" },
+        "at": {
+            "atMobiles": ["13800000000"],
+            "atUserIds": ["user001"],
+            "isAtAll": false,
+        },
+    }), requests[0].json());
+    assert_eq!(json!({ "isAtAll": true }), requests[1].json()["at"]);
+    assert_eq!(cached_json, requests[2].body);
+
+    let err = tokio_test::block_on(dt.send_with_at("[]", AtConfig { at_all: true, ..AtConfig::default() })).unwrap_err();
+    assert!(matches!(&err, DingTalkError::InvalidMessage(message) if message == "JSON message is not object"), "{}", err);
+    assert_eq!(3, server.requests().len());
+}
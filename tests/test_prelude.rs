@@ -0,0 +1,22 @@
+mod common;
+
+use dingtalk::prelude::*;
+use common::*;
+
+#[test]
+fn test_prelude() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::builder().webhook_url(&server.url).allow_insecure().access_token("abc").build().unwrap();
+    let markdown = MarkdownBuilder::new().bold("hello").build();
+    let result: XResult<()> = tokio_test::block_on(async {
+        dt.send_message(DingTalkMessage::new_markdown("title", &markdown)).await?;
+        dt.send_message(DingTalkMessage::new_action_card("title", "text")
+            .single_button(DingTalkMessageActionCardBtn { title: "btn".into(), action_url: "https://example.com/".into() })).await?;
+        dt.send_message(DingTalkMessage::new_feed_card()
+            .add_feed_card_link(DingTalkMessageFeedCardLink { title: "link".into(), message_url: "https://example.com/".into(), pic_url: "".into() })).await
+    });
+    result.unwrap();
+    assert_eq!(DingTalkType::DingTalk, dt.dingtalk_type);
+    assert_eq!(DingTalkMessageType::Text, DingTalkMessage::new_text("hello").kind());
+    assert!(matches!(DingTalkMessage::new_text("").validate(), Err(DingTalkError::InvalidMessage(_))));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::{ future::Future, pin::Pin, sync::{ Arc, Mutex }, time::Duration };
+use dingtalk::*;
+use common::*;
+
+/// Records sleep durations, then sleeps with `DefaultSleep`
+#[derive(Default)]
+struct RecordSleep {
+    durations: Mutex<Vec<Duration>>,
+}
+
+impl Sleep for RecordSleep {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.durations.lock().unwrap().push(duration);
+        DefaultSleep.sleep(duration)
+    }
+}
+
+#[test]
+fn test_rate_limiter_try_acquire() {
+    let rate_limiter = RateLimiter::per_minute(2).unwrap();
+    assert!(rate_limiter.try_acquire().is_ok());
+    assert!(rate_limiter.try_acquire().is_ok());
+    let wait = rate_limiter.try_acquire().unwrap_err();
+    assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30), "{:?}", wait);
+}
+
+#[test]
+fn test_send_rate_limit_fail_fast() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let mut dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.set_rate_limiter(Arc::new(RateLimiter::per_minute(2).unwrap().fail_fast()));
+    let cloned_dt = dt.clone();
+    tokio_test::block_on(dt.send_text("1")).unwrap();
+    tokio_test::block_on(cloned_dt.send_text("2")).unwrap();
+    let err = tokio_test::block_on(dt.send_text("3")).unwrap_err();
+
+    assert!(matches!(err, DingTalkError::RateLimited { retry_after: Some(_) }), "{}", err);
+    assert_eq!(2, server.requests().len());
+}
+
+#[test]
+fn test_send_rate_limit_wait() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let sleep = Arc::new(RecordSleep::default());
+    let mut dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.set_rate_limiter(Arc::new(RateLimiter::new(1, Duration::from_millis(50)).unwrap()));
+    dt.set_sleep(sleep.clone());
+    tokio_test::block_on(dt.send_text("1")).unwrap();
+    tokio_test::block_on(dt.send_text("2")).unwrap();
+
+    assert_eq!(2, server.requests().len());
+    let durations = sleep.durations.lock().unwrap();
+    assert!(!durations.is_empty());
+    assert!(durations.iter().all(|d| *d <= Duration::from_millis(50)), "{:?}", durations);
+}
+
+#[test]
+fn test_rate_limit_zero_is_invalid() {
+    let expected = "rate limit must be at least 1 message per period";
+    let results = vec![
+        RateLimiter::per_minute(0).map(|_| ()),
+        DingTalk::new("abc", "").with_rate_limit(0).map(|_| ()),
+        DingTalk::builder().access_token("abc").rate_limit(0).build().map(|_| ()),
+        DingTalk::from_config(serde_json::from_str(r#"{"access_token": "abc", "rate_limit_per_minute": 0}"#).unwrap()).map(|_| ()),
+    ];
+    for result in results {
+        match result {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!(expected, message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    match RateLimiter::new(1, Duration::from_secs(0)) {
+        Err(DingTalkError::InvalidConfig(message)) => assert_eq!("rate limit period must not be zero", message),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
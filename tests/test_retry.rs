@@ -0,0 +1,146 @@
+mod common;
+
+use std::{ future::Future, pin::Pin, sync::{ Arc, Mutex }, thread, time::Duration };
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_retry_budget_exhausted() {
+    let budget = Arc::new(RetryBudget::new(3, Duration::from_secs(60)));
+    let handles: Vec<_> = (0..2).map(|_| {
+        let budget = budget.clone();
+        thread::spawn(move || (0..5).filter(|_| budget.try_acquire()).count())
+    }).collect();
+    let acquired: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(3, acquired);
+    assert_eq!(0, budget.remaining());
+    assert!(!budget.try_acquire());
+}
+
+#[test]
+fn test_retry_budget_window_reset() {
+    let budget = RetryBudget::new(1, Duration::from_millis(20));
+    assert!(budget.try_acquire());
+    assert!(!budget.try_acquire());
+    thread::sleep(Duration::from_millis(30));
+    assert!(budget.try_acquire());
+}
+
+/// Records sleep durations, returns immediately
+#[derive(Default)]
+struct RecordSleep {
+    durations: Mutex<Vec<Duration>>,
+}
+
+impl Sleep for RecordSleep {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.durations.lock().unwrap().push(duration);
+        Box::pin(async {})
+    }
+}
+
+fn retry_dingtalk(url: &str, retry_policy: RetryPolicy) -> (DingTalk, Arc<RecordSleep>) {
+    let sleep = Arc::new(RecordSleep::default());
+    let mut dt = DingTalk::from_url(url).allow_insecure();
+    dt.set_retry_policy(retry_policy);
+    dt.set_sleep(sleep.clone());
+    (dt, sleep)
+}
+
+#[test]
+fn test_retry_policy_backoff() {
+    let retry_policy = RetryPolicy::new(5, Duration::from_millis(100)).max_delay(Duration::from_millis(300));
+    for (retry, max) in [(0, 100), (1, 200), (2, 300), (3, 300)].iter() {
+        let delay = retry_policy.backoff(*retry);
+        assert!(delay >= Duration::from_millis(max / 2) && delay <= Duration::from_millis(*max), "{} {:?}", retry, delay);
+    }
+}
+
+#[test]
+fn test_send_retry_5xx() {
+    let server = MockServer::start(vec![MockResponse::new(500, ""), MockResponse::new(503, ""), MockResponse::ok()]);
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(3, Duration::from_millis(100)));
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+
+    assert_eq!(3, server.requests().len());
+    let durations = sleep.durations.lock().unwrap();
+    assert_eq!(2, durations.len());
+    assert!(durations[0] >= Duration::from_millis(50) && durations[0] <= Duration::from_millis(100), "{:?}", durations);
+    assert!(durations[1] >= Duration::from_millis(100) && durations[1] <= Duration::from_millis(200), "{:?}", durations);
+}
+
+#[test]
+fn test_send_retry_exhausted() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(2, Duration::from_millis(10)));
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+
+    assert!(matches!(err, DingTalkError::Status(500)), "{}", err);
+    assert_eq!(3, server.requests().len());
+    assert_eq!(2, sleep.durations.lock().unwrap().len());
+}
+
+#[test]
+fn test_send_retry_after() {
+    let server = MockServer::start(vec![MockResponse::new(429, "").header("Retry-After", "2"), MockResponse::ok()]);
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(1, Duration::from_millis(10)));
+    tokio_test::block_on(dt.send_fast("{}")).unwrap();
+
+    assert_eq!(2, server.requests().len());
+    assert_eq!(vec![Duration::from_secs(2)], *sleep.durations.lock().unwrap());
+}
+
+#[test]
+fn test_send_no_retry_not_retryable() {
+    let responses = [MockResponse::new(400, ""), MockResponse::new(200, r#"{"errcode":310000,"errmsg":"keywords not in content"}"#)];
+    for response in responses.iter() {
+        let server = MockServer::start(vec![response.clone(), MockResponse::ok()]);
+        let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(3, Duration::from_millis(10)));
+        assert!(tokio_test::block_on(dt.send("{}")).is_err());
+        assert_eq!(1, server.requests().len());
+        assert!(sleep.durations.lock().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn test_send_retry_connect_error() {
+    let url = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let (dt, sleep) = retry_dingtalk(&url, RetryPolicy::new(2, Duration::from_millis(10)));
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+
+    assert!(matches!(err, DingTalkError::Http(_)), "{}", err);
+    assert_eq!(2, sleep.durations.lock().unwrap().len());
+}
+
+#[test]
+fn test_send_retry_budget() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+    let budget = Arc::new(RetryBudget::new(1, Duration::from_secs(60)));
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(3, Duration::from_millis(10)).budget(budget.clone()));
+    assert!(tokio_test::block_on(dt.send("{}")).is_err());
+
+    assert_eq!(2, server.requests().len());
+    assert_eq!(1, sleep.durations.lock().unwrap().len());
+    assert_eq!(0, budget.remaining());
+}
+
+#[test]
+fn test_send_retry_send_too_fast() {
+    let send_too_fast = MockResponse::new(200, r#"{"errcode":130101,"errmsg":"send too fast, exceed 20 times per minute"}"#);
+    let server = MockServer::start(vec![send_too_fast.clone(), MockResponse::ok()]);
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(2, Duration::from_millis(10)));
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    assert_eq!(2, server.requests().len());
+    // waits cooldown instead of backoff
+    assert_eq!(vec![Duration::from_secs(60)], *sleep.durations.lock().unwrap());
+
+    let server = MockServer::start(vec![send_too_fast]);
+    let (dt, sleep) = retry_dingtalk(&server.url, RetryPolicy::new(1, Duration::from_millis(10)));
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(60)), "{}", err);
+    assert_eq!(2, server.requests().len());
+    assert_eq!(1, sleep.durations.lock().unwrap().len());
+}
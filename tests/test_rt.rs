@@ -0,0 +1,18 @@
+use std::time::{ Duration, Instant };
+use dingtalk::*;
+
+#[test]
+fn test_default_sleep() {
+    let start = Instant::now();
+    tokio_test::block_on(DefaultSleep.sleep(Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+/// Timer thread wakes the task, no tokio runtime, run with `--no-default-features --features default-tls`
+#[cfg(not(feature = "tokio"))]
+#[test]
+fn test_default_sleep_without_tokio() {
+    let start = Instant::now();
+    futures::executor::block_on(DefaultSleep.sleep(Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
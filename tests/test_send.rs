@@ -0,0 +1,440 @@
+mod common;
+
+use std::time::{ Duration, Instant };
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_send_fast_not_read_body() {
+    // body never completes, reading body would hang
+    let server = MockServer::start(vec![MockResponse::ok().content_length(1_000_000)]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let start = Instant::now();
+    tokio_test::block_on(dt.send_fast(r#"{"msgtype":"text","text":{"content":"fast"}}"#)).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(r#"{"msgtype":"text","text":{"content":"fast"}}"#, server.requests()[0].body);
+}
+
+#[test]
+fn test_send_fast_status() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let err = tokio_test::block_on(dt.send_fast("{}")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Status(500)), "{}", err);
+}
+
+#[test]
+fn test_send_signature_required() {
+    let server = MockServer::start(vec![MockResponse::new(200,
+        r#"{"errcode":310000,"errmsg":"sign not match, more: [https://ding-doc.dingtalk.com/doc#/serverapi2/qf2nxq]"}"#)]);
+    let mut dt = DingTalk::new("token", "");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    match &err {
+        DingTalkError::SignatureRequired(errmsg) => assert!(errmsg.starts_with("sign not match")),
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert!(err.to_string().contains("sec_token"), "{}", err);
+}
+
+#[test]
+fn test_send_cloned_message() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let template = DingTalkMessage::new_action_card("action card title", "action card text");
+    let card_1 = template.clone_with(|m| m.single_button(DingTalkMessageActionCardBtn {
+        title: "open".into(),
+        action_url: "https://example.com/1".into(),
+    }));
+    let card_2 = template.clone_with(|m| m.single_button(DingTalkMessageActionCardBtn {
+        title: "open".into(),
+        action_url: "https://example.com/2".into(),
+    }));
+    assert!(template.action_card_single_btn.is_none());
+    tokio_test::block_on(async {
+        dt.send_message(card_1).await?;
+        dt.send_message(card_2).await
+    }).unwrap();
+
+    let requests = server.requests();
+    assert_eq!("https://example.com/1", requests[0].json()["actionCard"]["singleURL"]);
+    assert_eq!("https://example.com/2", requests[1].json()["actionCard"]["singleURL"]);
+    assert_eq!("action card title", requests[1].json()["actionCard"]["title"]);
+}
+
+#[test]
+fn test_send_log_file_not_utf8() {
+    let f = std::env::temp_dir().join(format!("dingtalk-test-binary-{}.log", std::process::id()));
+    std::fs::write(&f, [0x00_u8, 0xff, 0xfe, 0x80]).unwrap();
+    let f = f.to_str().unwrap().to_owned();
+
+    // error before sending, server is never hit
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let err = tokio_test::block_on(dt.send_log_file(&f)).unwrap_err();
+    let err_markdown = tokio_test::block_on(dt.send_markdown_from_file("title", &f)).unwrap_err();
+    std::fs::remove_file(&f).unwrap();
+
+    match &err {
+        DingTalkError::InvalidContent(message) => assert!(message.contains(&f), "{}", message),
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert!(err_markdown.to_string().contains("not valid UTF-8"), "{}", err_markdown);
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn test_send_with_outcome_signed() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let mut dt = DingTalk::new("token", "secret");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    let outcome = tokio_test::block_on(dt.send_with_outcome("{}")).unwrap();
+    assert!(outcome.signed);
+    assert_eq!("127.0.0.1", outcome.host);
+    assert!(server.requests()[0].query("sign").is_some());
+
+    let mut dt = DingTalk::new("token", "");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    let outcome = tokio_test::block_on(dt.send_with_outcome("{}")).unwrap();
+    assert!(!outcome.signed);
+    assert!(server.requests()[1].query("sign").is_none());
+}
+
+fn send_rate_limited(retry_after: &str) -> Option<Duration> {
+    let server = MockServer::start(vec![MockResponse::new(429, "").header("Retry-After", retry_after)]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+    match &err {
+        DingTalkError::RateLimited { retry_after } => *retry_after,
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_send_rate_limited_retry_after() {
+    assert_eq!(Some(Duration::from_secs(120)), send_rate_limited("120"));
+    let retry_after = send_rate_limited("Wed, 21 Oct 2099 07:28:00 GMT").unwrap();
+    assert!(retry_after > Duration::from_secs(365 * 24 * 3600), "{:?}", retry_after);
+    assert_eq!(Some(Duration::from_secs(0)), send_rate_limited("Wed, 21 Oct 2015 07:28:00 GMT"));
+    assert_eq!(None, send_rate_limited("soon"));
+}
+
+#[test]
+fn test_send_dead_letter() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+    let dead_letter_server = MockServer::start(vec![MockResponse::ok()]);
+    let mut dt = DingTalk::from_url(&server.url).allow_insecure();
+    let mut dead_letter = DingTalk::from_url(&dead_letter_server.url).allow_insecure();
+    // dead letter's own dead letter is never used
+    dead_letter.set_dead_letter(DingTalk::from_url(&server.url).allow_insecure());
+    dt.set_dead_letter(dead_letter);
+
+    let err = tokio_test::block_on(dt.send_text("alert")).unwrap_err();
+    assert!(err.to_string().contains("500"), "{}", err);
+
+    let dead_letter_requests = dead_letter_server.requests();
+    assert_eq!(1, dead_letter_requests.len());
+    let diagnostic = dead_letter_requests[0].json()["text"]["content"].as_str().unwrap().to_owned();
+    assert!(diagnostic.contains("Text"), "{}", diagnostic);
+    assert!(diagnostic.contains("127.0.0.1"), "{}", diagnostic);
+    assert!(diagnostic.contains("500"), "{}", diagnostic);
+    assert_eq!(1, server.requests().len());
+}
+
+#[test]
+fn test_send_dead_letter_also_fails() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+    let mut dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.set_dead_letter(DingTalk::from_url(&server.url).allow_insecure());
+    assert!(tokio_test::block_on(dt.send_text("alert")).is_err());
+    assert_eq!(2, server.requests().len());
+}
+
+#[test]
+fn test_send_with_options_header() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let options = SendOptions::default().header("X-Idempotency-Key", "alert-001");
+    tokio_test::block_on(async {
+        dt.send_message_with_options(DingTalkMessage::new_text("alert"), &options).await?;
+        dt.send_text("alert").await
+    }).unwrap();
+
+    let requests = server.requests();
+    assert_eq!(Some("alert-001"), requests[0].header("X-Idempotency-Key"));
+    assert_eq!(Some("application/json; charset=utf-8"), requests[0].header("Content-Type"));
+    assert_eq!(None, requests[1].header("X-Idempotency-Key"));
+}
+
+fn send_with_response(body: &str) -> XResult<()> {
+    let server = MockServer::start(vec![MockResponse::new(200, body)]);
+    let mut dt = DingTalk::new("token", "");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    tokio_test::block_on(dt.send("{}"))
+}
+
+#[test]
+fn test_send_errcode_number_or_string() {
+    assert!(send_with_response(r#"{"errcode":0,"errmsg":"ok"}"#).is_ok());
+    assert!(send_with_response(r#"{"errcode":"0","errmsg":"ok"}"#).is_ok());
+
+    let err = send_with_response(r#"{"errcode":"310000","errmsg":"sign not match"}"#).unwrap_err();
+    assert!(matches!(err, DingTalkError::SignatureRequired(_)), "{}", err);
+
+    let err = send_with_response(r#"{"errcode":"abc","errmsg":"ok"}"#).unwrap_err();
+    match &err {
+        DingTalkError::UnexpectedResponse(message) => assert!(message.contains("abc"), "{}", message),
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_send_api_error() {
+    let err = send_with_response(r#"{"errcode":310000,"errmsg":"keywords not in content"}"#).unwrap_err();
+    match &err {
+        DingTalkError::Api { code, message } => {
+            assert_eq!(310000, *code);
+            assert_eq!("keywords not in content", message);
+        },
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert!(err.to_string().contains("310000"), "{}", err);
+    assert!(err.to_string().contains("keywords not in content"), "{}", err);
+}
+
+#[test]
+fn test_send_custom_client() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let mut default_headers = reqwest::header::HeaderMap::new();
+    default_headers.insert("X-Client", reqwest::header::HeaderValue::from_static("custom"));
+    let client = reqwest::Client::builder().default_headers(default_headers).build().unwrap();
+    let mut dt = DingTalk::from_url(&server.url).allow_insecure();
+    dt.set_client(client.clone());
+    let cloned_dt = dt.clone();
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    tokio_test::block_on(cloned_dt.send_text("hello")).unwrap();
+    tokio_test::block_on(DingTalk::from_url(&server.url).allow_insecure().with_client(client).send_text("hello")).unwrap();
+
+    let requests = server.requests();
+    assert_eq!(3, requests.len());
+    assert!(requests.iter().all(|r| r.header("X-Client") == Some("custom")), "{:?}", requests);
+}
+
+#[test]
+fn test_send_timeout() {
+    let server = MockServer::start(vec![
+        MockResponse::ok().delay(Duration::from_millis(500)),
+        MockResponse::ok().content_length(100),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_timeout(Duration::from_millis(100));
+    // response is delayed
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Timeout), "{}", err);
+    // response body never completes
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Timeout), "{}", err);
+}
+
+#[test]
+fn test_send_http_error_redact_url() {
+    let url = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/robot/send", listener.local_addr().unwrap())
+    };
+    let dt = DingTalk::builder().access_token("secret_access_token").sec_token("sec").webhook_url(&url).allow_insecure().build().unwrap();
+    let err = tokio_test::block_on(dt.send("{}")).unwrap_err();
+
+    assert!(matches!(err, DingTalkError::Http(_)), "{}", err);
+    assert!(err.to_string().contains(&url), "{}", err);
+    assert!(!err.to_string().contains("secret_access_token"), "{}", err);
+    assert!(!err.to_string().contains("sign="), "{}", err);
+}
+
+#[test]
+fn test_send_keywords() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let keywords = vec!["[alert]".to_string()];
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_required_keywords(keywords.clone());
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(&err, DingTalkError::InvalidMessage(message) if message.contains("[alert]")), "{}", err);
+    assert!(server.requests().is_empty());
+
+    tokio_test::block_on(dt.send_text("[alert] hello")).unwrap();
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_keywords(keywords);
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    assert_eq!(2, server.requests().len());
+}
+
+#[test]
+fn test_send_with_token() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let mut dt = DingTalk::new("token", "");
+    dt.set_default_webhook_url(&server.url);
+    dt.allow_insecure = true;
+    tokio_test::block_on(async {
+        dt.send_text_with_token("token2", "hello").await?;
+        dt.send_message_with_token("token3", DingTalkMessage::new_text("hello")).await?;
+        dt.send_text("hello").await
+    }).unwrap();
+
+    let tokens: Vec<_> = server.requests().iter().map(|r| r.query("access_token").unwrap()).collect();
+    assert_eq!(vec!["token2", "token3", "token"], tokens);
+    assert_eq!("token", dt.access_token);
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc",
+        DingTalk::new("token", "").generate_signed_url_with_token("abc").unwrap());
+    assert!(!format!("{:?}", SendOptions::default().access_token("token2")).contains("token2"));
+}
+
+#[test]
+fn test_send_raw() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"errcode":0,"errmsg":"ok","messageId":"msg001"}"#),
+        MockResponse::new(200, r#"{"errcode":300001,"errmsg":"token is not exist"}"#),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let body = tokio_test::block_on(dt.send_raw(r#"{"msgtype":"text","text":{"content":"hello"}}"#)).unwrap();
+    assert_eq!(r#"{"errcode":0,"errmsg":"ok","messageId":"msg001"}"#, body);
+
+    let err = tokio_test::block_on(dt.send_raw(r#"{"msgtype":"text","text":{"content":"hello"}}"#)).unwrap_err();
+    assert!(matches!(err, DingTalkError::Api { code: 300001, .. }), "{}", err);
+}
+
+#[test]
+fn test_send_with_response() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"errcode":"0","errmsg":"ok"}"#),
+        MockResponse::new(200, "ok"),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let response = tokio_test::block_on(dt.send_with_response("{}")).unwrap();
+    assert_eq!(SendResponse { errcode: 0, errmsg: "ok".into() }, response);
+
+    let err = tokio_test::block_on(dt.send_with_response("{}")).unwrap_err();
+    assert!(matches!(err, DingTalkError::UnexpectedResponse(_)), "{}", err);
+    assert!(tokio_test::block_on(dt.send("{}")).is_ok());
+}
+
+#[test]
+fn test_send_many() {
+    let server = MockServer::start(vec![
+        MockResponse::ok().delay(Duration::from_millis(100)),
+        MockResponse::new(500, "error").delay(Duration::from_millis(100)),
+        MockResponse::ok().delay(Duration::from_millis(100)),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let dingtalk_messages: Vec<_> = (0..8).map(|i| DingTalkMessage::new_text(&format!("message {}", i))).collect();
+    let results = tokio_test::block_on(dt.send_many(&dingtalk_messages, 3));
+
+    assert_eq!(8, results.len());
+    assert_eq!(1, results.iter().filter(|r| r.is_err()).count());
+    assert_eq!(8, server.requests().len());
+    assert!(server.max_in_flight() <= 3, "max in flight: {}", server.max_in_flight());
+    assert!(server.max_in_flight() > 1, "not concurrent");
+}
+
+#[test]
+fn test_send_many_order() {
+    let server = MockServer::start(vec![MockResponse::ok().delay(Duration::from_millis(200))]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let dingtalk_messages = [DingTalkMessage::new_text("slow"), DingTalkMessage::new_text("")];
+    let results = tokio_test::block_on(dt.send_many(&dingtalk_messages, 2));
+    // invalid message fails before the first send finishes, results are still in message order
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(DingTalkError::InvalidMessage(_))));
+}
+
+#[test]
+fn test_send_message_from_short_lived_data() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let template = DingTalkMessage::new_link("title", "", "", "https://example.com/");
+    tokio_test::block_on(async {
+        for i in 0..2 {
+            // message borrows nothing from `dt`, local data may be dropped before `dt`
+            let link_text = format!("build {}", i);
+            dt.send_message(template.clone_with(|m| m.link("title", &link_text, "", "https://example.com/"))).await?;
+            dt.send_link("title", &link_text, "", "https://example.com/").await?;
+        }
+        Ok::<_, DingTalkError>(())
+    }).unwrap();
+    assert_eq!("build 1", server.requests()[3].json()["link"]["text"]);
+}
+
+#[test]
+fn test_send_with_metrics() {
+    let server = MockServer::start(vec![
+        MockResponse::new(500, "error"),
+        MockResponse::ok().delay(Duration::from_millis(100)),
+        MockResponse::ok(),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure().with_retries(2, Duration::from_millis(10));
+    let metrics = tokio_test::block_on(dt.send_with_metrics("{}")).unwrap();
+    assert_eq!(2, metrics.attempts);
+    assert_eq!(Some(200), metrics.status);
+    assert!(metrics.elapsed >= Duration::from_millis(100), "{:?}", metrics);
+
+    let metrics = tokio_test::block_on(dt.send_with_metrics("{}")).unwrap();
+    assert_eq!(1, metrics.attempts);
+
+    // not sent
+    let dt = DingTalk::builder().direct_url(&server.url).allow_insecure().dry_run(true).build().unwrap();
+    let metrics = tokio_test::block_on(dt.send_with_metrics("{}")).unwrap();
+    assert_eq!((0, None), (metrics.attempts, metrics.status));
+    assert_eq!(3, server.requests().len());
+}
+
+#[test]
+fn test_send_text_chunked() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let lines: Vec<String> = (0..1000).map(|i| format!("{:04} at com.example.Service.handle(Service.java:42)", i)).collect();
+    let text = lines.join("\n");
+    assert!(text.len() > 40000);
+    tokio_test::block_on(dt.send_text_chunked(&text)).unwrap();
+
+    let contents: Vec<String> = server.requests().iter().map(|r| r.json()["text"]["content"].as_str().unwrap().to_owned()).collect();
+    assert_eq!(3, contents.len());
+    let mut sent_lines = vec![];
+    for (i, content) in contents.iter().enumerate() {
+        assert!(content.len() <= 20000, "{}", content.len());
+        let prefix = format!("({}/3) ", i + 1);
+        assert!(content.starts_with(&prefix), "{}", &content[..20]);
+        sent_lines.extend(content[prefix.len()..].split('\n').map(|l| l.to_owned()));
+    }
+    // split on line boundaries, in order
+    assert_eq!(lines, sent_lines);
+
+    // a long line is split on char boundaries
+    tokio_test::block_on(dt.send_text_chunked(&"中".repeat(10000))).unwrap();
+    let contents: Vec<String> = server.requests()[3..].iter().map(|r| r.json()["text"]["content"].as_str().unwrap().to_owned()).collect();
+    assert_eq!(2, contents.len());
+    assert_eq!(10000, contents.iter().map(|c| c.split(' ').nth(1).unwrap().chars().count()).sum::<usize>());
+
+    // short text is sent as is
+    tokio_test::block_on(dt.send_text_chunked("hello\n")).unwrap();
+    assert_eq!("hello\n", server.requests()[5].json()["text"]["content"]);
+    let err = tokio_test::block_on(dt.send_text_chunked("")).unwrap_err();
+    assert!(matches!(err, DingTalkError::InvalidMessage(_)), "{}", err);
+}
+
+#[test]
+fn test_send_text_chunked_boundary() {
+    let transport = std::sync::Arc::new(MockTransport::new());
+    let dt = DingTalk::new("abc", "").with_transport(transport.clone());
+    let text = "x".repeat(20000);
+    tokio_test::block_on(dt.send_text_chunked(&text)).unwrap();
+    assert_eq!(1, transport.requests().len());
+    assert_eq!(dt.build_message_json(&DingTalkMessage::new_text(&text)).unwrap(), transport.sent_messages()[0]);
+
+    tokio_test::block_on(dt.send_text_chunked(&"x".repeat(20001))).unwrap();
+    let contents: Vec<String> = transport.sent_messages()[1..].iter()
+        .map(|m| serde_json::from_str::<serde_json::Value>(m).unwrap()["text"]["content"].as_str().unwrap().to_owned()).collect();
+    assert_eq!(2, contents.len());
+    assert!(contents[0].starts_with("(1/2) ") && contents[1].starts_with("(2/2) "), "{:?}", &contents[1][..10]);
+    assert!(contents.iter().all(|c| c.len() <= 20000));
+    assert_eq!(20001, contents.iter().map(|c| c.len() - "(1/2) ".len()).sum::<usize>());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use dingtalk::*;
+use common::*;
+
+#[test]
+fn test_send_stream() {
+    let server = MockServer::start(vec![
+        MockResponse::ok(),
+        MockResponse::new(200, r#"{"errcode":310000,"errmsg":"keywords not in content"}"#),
+        MockResponse::ok(),
+    ]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    let messages = futures::stream::iter(vec![
+        DingTalkMessage::new_text("alert 1"),
+        DingTalkMessage::new_text("alert 2"),
+        DingTalkMessage::new_text("alert 3"),
+    ]);
+    // failed message does not stop sending
+    tokio_test::block_on(dt.send_stream(messages));
+    let contents: Vec<_> = server.requests().iter().map(|r| r.json()["text"]["content"].as_str().unwrap().to_owned()).collect();
+    assert_eq!(vec!["alert 1", "alert 2", "alert 3"], contents);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_spawn_sender() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let dt = DingTalk::from_url(&server.url).allow_insecure();
+    tokio_test::block_on(async {
+        let (mut tx, rx) = tokio::sync::mpsc::channel(10);
+        let handle = dt.spawn_sender(rx);
+        for i in 0..3 {
+            tx.send(DingTalkMessage::new_text(&format!("alert {}", i))).await.unwrap();
+        }
+        // task ends after all senders are dropped
+        drop(tx);
+        handle.await.unwrap();
+    });
+    assert_eq!(3, server.requests().len());
+}
@@ -0,0 +1,178 @@
+use std::{ sync::Arc, time::{ Duration, UNIX_EPOCH } };
+use dingtalk::*;
+
+#[test]
+fn test_signed_url_default() {
+    let dt = DingTalk::new("abc", "");
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc", dt.generate_signed_url().unwrap());
+    let dt = DingTalk::new_wechat("abc");
+    assert_eq!("https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key=abc", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_signed_url_custom_params() {
+    let mut dt = DingTalk::new("abc", "");
+    dt.set_webhook_base_path("https://gateway.example.com/", "/proxy/dingtalk");
+    dt.set_webhook_params(DingTalkWebhookParams {
+        token: Some("token".into()),
+        ..Default::default()
+    });
+    assert_eq!("https://gateway.example.com/proxy/dingtalk?token=abc", dt.generate_signed_url().unwrap());
+
+    let mut dt = DingTalk::new("abc", "secret");
+    dt.set_webhook_params(DingTalkWebhookParams {
+        token: Some("token".into()),
+        timestamp: Some("ts".into()),
+        sign: Some("signature".into()),
+    });
+    let signed_url = dt.generate_signed_url().unwrap();
+    assert!(signed_url.starts_with("https://oapi.dingtalk.com/robot/send?token=abc&ts="), "{}", signed_url);
+    assert!(signed_url.contains("&signature="), "{}", signed_url);
+}
+
+#[test]
+fn test_signed_url_fixed_clock() {
+    let mut dt = DingTalk::new("abc", "SEC0123456789");
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000000))));
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc\
+        &timestamp=1600000000000&sign=nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8%3D", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_signed_url_clock_before_epoch() {
+    let mut dt = DingTalk::new("abc", "SEC0123456789");
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH - Duration::from_secs(1))));
+    let err = dt.generate_signed_url().unwrap_err();
+    assert!(matches!(err, DingTalkError::Clock(_)), "{}", err);
+
+    // unsigned URL does not need clock
+    dt.sec_token = "".into();
+    assert!(dt.generate_signed_url().is_ok());
+}
+
+#[test]
+fn test_verify_signature() {
+    let sign = "nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8=";
+    assert!(verify_signature("SEC0123456789", "1600000000000", sign));
+    assert!(!verify_signature("SEC0123456789", "1600000000001", sign));
+    assert!(!verify_signature("SEC012345678", "1600000000000", sign));
+    assert!(!verify_signature("SEC0123456789", "1600000000000", "mVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8="));
+    assert!(!verify_signature("SEC0123456789", "1600000000000", "not base64!"));
+}
+
+#[test]
+fn test_set_access_token_and_sec_token() {
+    let mut dt = DingTalk::new("abc", "");
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000000))));
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc", dt.generate_signed_url().unwrap());
+
+    dt.set_access_token("def");
+    dt.set_sec_token("SEC0123456789");
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=def\
+        &timestamp=1600000000000&sign=nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8%3D", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_signed_url_base_with_query() {
+    let cases = [
+        ("https://gateway.example.com/send", "https://gateway.example.com/send?access_token=abc"),
+        ("https://gateway.example.com/send?", "https://gateway.example.com/send?access_token=abc"),
+        ("https://gateway.example.com/send?tenant=ops", "https://gateway.example.com/send?tenant=ops&access_token=abc"),
+        ("https://gateway.example.com/send?tenant=ops&", "https://gateway.example.com/send?tenant=ops&access_token=abc"),
+    ];
+    for (webhook_url, expected) in cases.iter() {
+        let mut dt = DingTalk::new("abc", "");
+        dt.set_default_webhook_url(webhook_url);
+        assert_eq!(*expected, dt.generate_signed_url().unwrap());
+    }
+
+    let mut dt = DingTalk::new("abc", "SEC0123456789");
+    dt.set_default_webhook_url("https://gateway.example.com/send?tenant=ops");
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000000))));
+    assert_eq!("https://gateway.example.com/send?tenant=ops&access_token=abc\
+        &timestamp=1600000000000&sign=nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8%3D", dt.generate_signed_url().unwrap());
+}
+
+#[test]
+fn test_signed_url_percent_encoding() {
+    // sign `CaSojvDHVTHiLQ/UHTAJrlrtMg/Y9BP64db1MP+gaC8=` has `+`, `/` and `=`
+    let mut dt = DingTalk::new("a+b/c=d&e", "SEC0123456789");
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000010))));
+    let signed_url = dt.generate_signed_url().unwrap();
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=a%2Bb%2Fc%3Dd%26e\
+        &timestamp=1600000000010&sign=CaSojvDHVTHiLQ%2FUHTAJrlrtMg%2FY9BP64db1MP%2BgaC8%3D", signed_url);
+
+    let url = reqwest::Url::parse(&signed_url).unwrap();
+    let query: std::collections::HashMap<_, _> = url.query_pairs().collect();
+    assert_eq!("a+b/c=d&e", query["access_token"]);
+    assert!(verify_signature("SEC0123456789", &query["timestamp"], &query["sign"]));
+}
+
+#[test]
+fn test_sign_hmac_sha256_known_output() {
+    // Base64(HmacSHA256("1234567890123\n" + secret)) with key secret, calculated by Python `hmac`
+    let sec_token = "SECabcdefghijklmnopqrstuvwxyz0123456789";
+    let sign = "3t6CcL1rVeKNkW6SWL0pAjjiSQKcVgnnPzyqJJByS0U=";
+    let mut dt = DingTalk::new("abc", sec_token);
+    dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1234567890123))));
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc\
+        &timestamp=1234567890123&sign=3t6CcL1rVeKNkW6SWL0pAjjiSQKcVgnnPzyqJJByS0U%3D", dt.generate_signed_url().unwrap());
+    assert!(verify_signature(sec_token, "1234567890123", sign));
+}
+
+#[test]
+fn test_sign_same_as_standard_hmac_sha256() {
+    // expected signs are standard HMAC-SHA256 as RFC 2104, which rust-crypto `Hmac<Sha256>` also produced,
+    // calculated by Python `hmac`
+    let cases = [
+        ("SEC", 0, "WXlo3SJR7RgN8FIiC1TQe7rGqMh6HlgwbQkRnH32XWg=", "WXlo3SJR7RgN8FIiC1TQe7rGqMh6HlgwbQkRnH32XWg%3D"),
+        ("SEC密钥🔑", 1600000000000, "QD2HkMhVsn0/UWuqjIMnCDVI43PLsfb6SuUCEPD7HgU=", "QD2HkMhVsn0%2FUWuqjIMnCDVI43PLsfb6SuUCEPD7HgU%3D"),
+    ];
+    for (sec_token, timestamp, sign, encoded_sign) in cases.iter() {
+        let mut dt = DingTalk::new("abc", sec_token);
+        dt.set_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(*timestamp))));
+        assert_eq!(format!("https://oapi.dingtalk.com/robot/send?access_token=abc&timestamp={}&sign={}", timestamp, encoded_sign),
+            dt.generate_signed_url().unwrap());
+        assert!(verify_signature(sec_token, &timestamp.to_string(), sign));
+    }
+}
+
+#[test]
+fn test_sign() {
+    let dt = DingTalk::new("abc", "SEC0123456789");
+    assert_eq!("nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8=", dt.sign(1600000000000));
+    assert_eq!("CaSojvDHVTHiLQ/UHTAJrlrtMg/Y9BP64db1MP+gaC8=", dt.sign(1600000000010));
+    assert!(verify_signature("SEC0123456789", "1600000000010", &dt.sign(1600000000010)));
+}
+
+#[test]
+fn test_base_host() {
+    let dt = DingTalk::new("abc", "").with_base_host("https://oapi.example.com/").unwrap();
+    assert_eq!("https://oapi.example.com/robot/send?access_token=abc", dt.generate_signed_url().unwrap());
+    let dt = DingTalk::new_wechat("abc").with_base_host("qyapi.example.com:8443").unwrap();
+    assert_eq!("https://qyapi.example.com:8443/cgi-bin/webhook/send?key=abc", dt.generate_signed_url().unwrap());
+    let dt = DingTalk::builder().base_host("oapi.example.com").dingtalk_type(DingTalkType::WeChatWork).access_token("abc").build().unwrap();
+    assert_eq!("https://oapi.example.com/cgi-bin/webhook/send", dt.default_webhook_url);
+
+    let cases = [
+        ("http://oapi.example.com", "base host must be https: http://oapi.example.com"),
+        ("https://oapi.example.com/robot/send", "base host must not have path or query: https://oapi.example.com/robot/send"),
+        ("https://oapi.example.com?a=b", "base host must not have path or query: https://oapi.example.com?a=b"),
+    ];
+    for (base_host, expected) in cases.iter() {
+        match DingTalk::new("abc", "").with_base_host(base_host) {
+            Err(DingTalkError::InvalidConfig(message)) => assert_eq!(*expected, message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    assert!(matches!(DingTalk::builder().base_host("https://").build(), Err(DingTalkError::InvalidConfig(_))));
+}
+
+#[test]
+fn test_signed_url_token_pre_encoded() {
+    let dt = DingTalk::builder().access_token("abc%2Bdef").build().unwrap();
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc%252Bdef", dt.generate_signed_url().unwrap());
+    let dt = DingTalk::builder().access_token("abc%2Bdef").token_pre_encoded(true).build().unwrap();
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc%2Bdef", dt.generate_signed_url().unwrap());
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=x%2By", dt.generate_signed_url_with_token("x%2By").unwrap());
+}
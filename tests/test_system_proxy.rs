@@ -0,0 +1,28 @@
+mod common;
+
+use std::env;
+use dingtalk::*;
+use common::*;
+
+// only test of this binary, `HTTP_PROXY` is process wide
+#[test]
+fn test_builder_use_system_proxy() {
+    let server = MockServer::start(vec![MockResponse::ok()]);
+    let proxy = MockServer::start(vec![MockResponse::ok()]);
+    env::set_var("HTTP_PROXY", &proxy.url);
+    env::set_var("http_proxy", &proxy.url);
+
+    let dt = DingTalk::builder().access_token("abc").webhook_url(&server.url).allow_insecure().use_system_proxy(true).build().unwrap();
+    tokio_test::block_on(dt.send_text("via proxy")).unwrap();
+    let dt = DingTalk::builder().access_token("abc").webhook_url(&server.url).allow_insecure().use_system_proxy(false).build().unwrap();
+    tokio_test::block_on(dt.send_text("direct")).unwrap();
+
+    env::remove_var("HTTP_PROXY");
+    env::remove_var("http_proxy");
+    let proxy_requests = proxy.requests();
+    assert_eq!(1, proxy_requests.len());
+    assert_eq!("via proxy", proxy_requests[0].json()["text"]["content"]);
+    let requests = server.requests();
+    assert_eq!(1, requests.len());
+    assert_eq!("direct", requests[0].json()["text"]["content"]);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "tracing")]
+mod common;
+
+use std::{ fmt::Debug, sync::{ Arc, Mutex, atomic::{ AtomicU64, Ordering } } };
+use tracing::{ Event, Metadata, field::{ Field, Visit }, span::{ Attributes, Id, Record } };
+use dingtalk::*;
+use common::*;
+
+/// Records spans and events as `name field=value ...` lines
+#[derive(Clone, Default)]
+struct RecordSubscriber {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct LineVisitor(String);
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push_str(&format!(" {}={:?}", field.name(), value));
+    }
+}
+
+impl tracing::Subscriber for RecordSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool { true }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut visitor = LineVisitor(span.metadata().name().to_owned());
+        span.record(&mut visitor);
+        self.lines.lock().unwrap().push(visitor.0);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = LineVisitor(event.metadata().level().to_string());
+        event.record(&mut visitor);
+        self.lines.lock().unwrap().push(visitor.0);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn test_tracing_redact_secrets() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"errcode":310000,"errmsg":"keywords not in content"}"#)]);
+    let dt = DingTalk::builder()
+        .access_token("secret_access_token")
+        .sec_token("secret_sec_token")
+        .webhook_url(&format!("{}/robot/send", server.url)).allow_insecure()
+        .build().unwrap();
+    let subscriber = RecordSubscriber::default();
+    let err = tracing::subscriber::with_default(subscriber.clone(), || {
+        tokio_test::block_on(dt.send_text("hello")).unwrap_err()
+    });
+    assert!(matches!(err, DingTalkError::Api { code: 310000, .. }), "{}", err);
+
+    let log = subscriber.lines.lock().unwrap().join("\n");
+    assert!(log.contains(&format!("dingtalk_send webhook={}/robot/send", server.url)), "{}", log);
+    assert!(log.contains("message_type=Text"), "{}", log);
+    assert!(log.contains("body_length="), "{}", log);
+    assert!(log.contains("status=200"), "{}", log);
+    assert!(log.contains("errcode=310000"), "{}", log);
+    assert!(!log.contains("secret_access_token"), "{}", log);
+    assert!(!log.contains("secret_sec_token"), "{}", log);
+    assert!(!log.contains("sign="), "{}", log);
+}
@@ -0,0 +1,88 @@
+use std::{ future::Future, pin::Pin, sync::{ Arc, atomic::{ AtomicU32, Ordering } }, time::{ Duration, UNIX_EPOCH } };
+use dingtalk::*;
+
+#[test]
+fn test_mock_transport_records_requests() {
+    let transport = Arc::new(MockTransport::new());
+    let dt = DingTalk::builder().access_token("abc").sec_token("SEC0123456789")
+        .clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_millis(1600000000000))))
+        .transport(transport.clone()).build().unwrap();
+    tokio_test::block_on(async {
+        dt.send_text("disk full").await?;
+        dt.send_markdown("title", "**disk full**").await
+    }).unwrap();
+
+    let requests = transport.requests();
+    assert_eq!(2, requests.len());
+    assert_eq!("https://oapi.dingtalk.com/robot/send?access_token=abc&timestamp=1600000000000&sign=nVDy2kli396sQV2feyDeIyiIPNCMwVHeJnppDT8gnf8%3D",
+        requests[0].url);
+    let message_json: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+    assert_eq!("disk full", message_json["text"]["content"]);
+    assert_eq!(dt.build_message_json(&DingTalkMessage::new_markdown("title", "**disk full**")).unwrap(),
+        transport.sent_messages()[1]);
+}
+
+#[test]
+fn test_mock_transport_error_response() {
+    let transport = Arc::new(MockTransport::with_response(r#"{"errcode":300001,"errmsg":"token is not exist"}"#));
+    let dt = DingTalk::new("abc", "").with_transport(transport.clone());
+    match tokio_test::block_on(dt.send_text("hello")) {
+        Err(DingTalkError::Api { code, message }) => {
+            assert_eq!(300001, code);
+            assert_eq!("token is not exist", message);
+        },
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(1, transport.requests().len());
+
+    // `send_fast` does not check errcode
+    tokio_test::block_on(dt.send_fast("{}")).unwrap();
+    assert_eq!("{}", transport.sent_messages()[1]);
+}
+
+#[test]
+fn test_mock_transport_records_headers() {
+    let transport = Arc::new(MockTransport::new());
+    let dt = DingTalk::builder().access_token("abc").with_header("X-Api-Key", "key123")
+        .with_content_type("application/json").transport(transport.clone()).build().unwrap();
+    tokio_test::block_on(dt.send_with_options("{}", &SendOptions::default().header("X-Trace-Id", "trace1"))).unwrap();
+    tokio_test::block_on(dt.send_fast("{}")).unwrap();
+
+    let requests = transport.requests();
+    assert_eq!(2, requests.len());
+    assert_eq!("application/json", requests[0].headers["content-type"]);
+    assert_eq!("key123", requests[0].headers["x-api-key"]);
+    assert_eq!("trace1", requests[0].headers["x-trace-id"]);
+    assert_eq!("key123", requests[1].headers["x-api-key"]);
+    assert!(requests[1].headers.get("x-trace-id").is_none());
+}
+
+#[derive(Default)]
+struct UnavailableTransport {
+    attempts: AtomicU32,
+}
+
+impl Transport for UnavailableTransport {
+    fn post(&self, _url: &str, _headers: &reqwest::header::HeaderMap, _body: &str) -> Pin<Box<dyn Future<Output = XResult<String>> + Send + '_>> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        Box::pin(async { Err(DingTalkError::Status(503)) })
+    }
+}
+
+#[test]
+fn test_custom_transport_is_retried() {
+    let transport = Arc::new(UnavailableTransport::default());
+    let dt = DingTalk::builder().access_token("abc").transport(transport.clone())
+        .retries(2, Duration::from_millis(1)).build().unwrap();
+    let err = tokio_test::block_on(dt.send_text("hello")).unwrap_err();
+    assert!(matches!(err, DingTalkError::Status(503)), "{}", err);
+    assert_eq!(3, transport.attempts.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_dry_run_skips_transport() {
+    let transport = Arc::new(MockTransport::new());
+    let dt = DingTalk::builder().access_token("abc").transport(transport.clone()).dry_run(true).build().unwrap();
+    tokio_test::block_on(dt.send_text("hello")).unwrap();
+    assert!(transport.requests().is_empty());
+}
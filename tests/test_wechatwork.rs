@@ -1,6 +1,8 @@
 use dingtalk::*;
 
+/// Sends to real robot, requires token file, run with `cargo test -- --ignored`
 #[test]
+#[ignore]
 fn run_all_tests_wechat_work() {
     tokio_test::block_on(_test_send_wechat_work()).unwrap();
 }